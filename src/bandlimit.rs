@@ -0,0 +1,173 @@
+// Decides which partials of a fundamental may be rendered without aliasing. Partials at or above
+// Nyquist are dropped, and `rolloff` fades the partials in the top fraction of the band out
+// smoothly so that a partial crossing Nyquist under pitch modulation does not click in and out.
+// Fundamentals below this, down to the 0 Hz a phase LFO at full depth passes through, get as many
+// partials as this one so that the tails stay finite. MIDI note 0 is just above it.
+const MIN_FUNDAMENTAL: f32 = 8.0;
+
+pub(crate) struct BandLimit {
+    fundamental: f32,
+    nyquist: f32,
+    rolloff_width: f32,
+}
+
+impl BandLimit {
+    pub(crate) fn new(fundamental: f32, sample_rate: f32, rolloff: f32) -> Self {
+        let nyquist = sample_rate / 2.0;
+        Self {
+            fundamental,
+            nyquist,
+            rolloff_width: nyquist * rolloff,
+        }
+    }
+
+    // The same limit with the roll-off narrowed to the top `partials` partials below Nyquist, for
+    // renderers that pay for every partial inside it.
+    pub(crate) fn with_rolloff_partials(&self, partials: usize) -> Self {
+        Self {
+            rolloff_width: self.rolloff_width.min(partials as f32 * self.fundamental.max(MIN_FUNDAMENTAL)),
+            ..*self
+        }
+    }
+
+    // Exclusive upper bound of the partial indices that lie below Nyquist.
+    pub(crate) fn end(&self) -> usize {
+        (self.nyquist / self.fundamental.max(MIN_FUNDAMENTAL)).ceil() as usize
+    }
+
+    // First partial index that the roll-off attenuates.
+    pub(crate) fn rolloff_start(&self) -> usize {
+        ((self.nyquist - self.rolloff_width) / self.fundamental.max(MIN_FUNDAMENTAL)).floor() as usize + 1
+    }
+
+    pub(crate) fn gain(&self, partial: usize) -> f32 {
+        self.gain_at(partial as f32)
+    }
+
+    // Same as `gain`, for a partial at an arbitrary multiple of the fundamental.
+    pub(crate) fn gain_at(&self, ratio: f32) -> f32 {
+        let headroom = self.nyquist - ratio * self.fundamental;
+        if headroom <= 0.0 {
+            0.0
+        } else if headroom >= self.rolloff_width {
+            1.0
+        } else {
+            let t = headroom / self.rolloff_width;
+            t * t * (3.0 - 2.0 * t)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{bin_energy, block, render, render_block, SAMPLE_RATE};
+    use crate::{OscMode, Waveform, MAX_BLOCK_SIZE};
+
+    // Relative energy outside the expected harmonics, well above the f32 phase noise of the renders.
+    const ALIAS_FLOOR: f64 = 1e-4;
+
+    // Energy left over after removing DC and every harmonic of `freq` below Nyquist. Anything that
+    // folded back from above Nyquist lands somewhere else and shows up here.
+    fn aliased_energy(signal: &[f32], freq: f32) -> f64 {
+        let total = signal.iter().map(|x| *x as f64 * *x as f64).sum::<f64>() / signal.len() as f64;
+        if total == 0.0 {
+            // A tail with no partials below Nyquist is silent.
+            return 0.0;
+        }
+        let mut harmonic = bin_energy(signal, 0.0);
+        let mut k = 1;
+        while k as f32 * freq < SAMPLE_RATE / 2.0 {
+            harmonic += bin_energy(signal, k as f32 * freq);
+            k += 1;
+        }
+        (total - harmonic) / total
+    }
+
+
+    #[test]
+    fn end_excludes_nyquist() {
+        assert_eq!(BandLimit::new(12000.0, SAMPLE_RATE, 0.0).end(), 2);
+        assert_eq!(BandLimit::new(11000.0, SAMPLE_RATE, 0.0).end(), 3);
+        assert_eq!(BandLimit::new(30000.0, SAMPLE_RATE, 0.0).end(), 1);
+    }
+
+    #[test]
+    fn end_is_finite_at_zero_hz() {
+        assert_eq!(BandLimit::new(0.0, SAMPLE_RATE, 0.0).end(), 3000);
+    }
+
+    #[test]
+    fn phase_lfo_through_zero_hz_stays_finite() {
+        // The odd/even balance puts the tail on its shaped path.
+        for odd_even in [0.0, 0.5] {
+            for osc_mode in [OscMode::Exact, OscMode::Fast] {
+                let mut block = block(1.0, Waveform::Sawtooth, osc_mode, 0.0);
+                block.odd_even.fill(odd_even);
+                // A full depth LFO on the phase, reaching exactly 0 Hz halfway through the block.
+                for (n, phase_mod) in block.phase_mod.iter_mut().enumerate() {
+                    *phase_mod = (n as f32 / (MAX_BLOCK_SIZE / 2) as f32 - 1.0).abs();
+                }
+                assert!(render_block(&block, 220.0).iter().all(|x| x.is_finite()));
+            }
+        }
+    }
+
+    #[test]
+    fn rolloff_fades_to_zero_at_nyquist() {
+        let limit = BandLimit::new(1000.0, SAMPLE_RATE, 0.25);
+        assert_eq!(limit.gain(10), 1.0);
+        assert_eq!(limit.gain(18), 1.0);
+        assert!(limit.gain(21) > 0.0 && limit.gain(21) < 1.0);
+        assert!(limit.gain(22) < limit.gain(21));
+        assert_eq!(limit.gain(24), 0.0);
+        assert_eq!(limit.gain(30), 0.0);
+    }
+
+    #[test]
+    fn narrowed_rolloff_spans_at_most_the_given_partials() {
+        let limit = BandLimit::new(20.0, SAMPLE_RATE, 0.5);
+        assert_eq!(limit.end() - limit.rolloff_start(), 599);
+        let narrowed = limit.with_rolloff_partials(128);
+        assert_eq!(narrowed.end() - narrowed.rolloff_start(), 127);
+        assert_eq!(narrowed.gain(narrowed.rolloff_start() - 1), 1.0);
+        // A roll-off that is narrow enough already is left alone.
+        let limit = BandLimit::new(1000.0, SAMPLE_RATE, 0.25);
+        assert_eq!(limit.with_rolloff_partials(128).rolloff_start(), limit.rolloff_start());
+    }
+
+    #[test]
+    fn high_notes_do_not_alias() {
+        for freq in [7000.0, 9000.0, 13000.0, 23000.0] {
+            for osc_mode in [OscMode::Exact, OscMode::Fast] {
+                let signal = render(freq, 1.0, Waveform::None, osc_mode, 0.0);
+                let aliased = aliased_energy(&signal, freq);
+                assert!(aliased < ALIAS_FLOOR, "{freq} Hz: {aliased}");
+            }
+        }
+    }
+
+    #[test]
+    fn waveform_tail_does_not_alias() {
+        for waveform in [Waveform::Sawtooth, Waveform::Square, Waveform::Triangle] {
+            for freq in [220.0, 330.0, 510.0, 740.0] {
+                for osc_mode in [OscMode::Exact, OscMode::Fast] {
+                    let signal = render(freq, 0.0, waveform, osc_mode, 0.0);
+                    let aliased = aliased_energy(&signal, freq);
+                    assert!(aliased < ALIAS_FLOOR, "{freq} Hz: {aliased}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rolloff_does_not_alias() {
+        let signal = render(3110.0, 1.0, Waveform::Sawtooth, OscMode::Exact, 0.2);
+        assert!(aliased_energy(&signal, 3110.0) < ALIAS_FLOOR);
+        // The 7th partial (21770 Hz) sits inside the roll-off band and is attenuated.
+        let full = render(3110.0, 1.0, Waveform::Sawtooth, OscMode::Exact, 0.0);
+        assert!(bin_energy(&signal, 7.0 * 3110.0) < bin_energy(&full, 7.0 * 3110.0));
+        let fundamental = bin_energy(&signal, 3110.0) / bin_energy(&full, 3110.0);
+        assert!((fundamental - 1.0).abs() < 1e-4);
+    }
+}
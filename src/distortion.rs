@@ -0,0 +1,48 @@
+use nih_plug::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+pub(crate) enum PhaseDistortion {
+    Off,
+    Saw,
+    Resonant,
+}
+
+// Roll-off the warped bank gets at least, so that its partials fade in and out as the amount
+// moves the limit instead of popping.
+pub(crate) const MIN_ROLLOFF: f32 = 0.25;
+
+// Where the Saw curve reaches half a cycle. The knee stops at a tenth of a cycle, where the bank
+// is read five times faster and still keeps a useful number of partials.
+fn knee(amount: f32) -> f32 {
+    0.5 - 0.4 * amount
+}
+
+// Casio CZ style phase distortion of a harmonic bank. Returns the phase to read the bank at and
+// a gain for the value read, for `phase` in [0, 1) and `amount` between 0 and 1.
+pub(crate) fn warp(curve: PhaseDistortion, phase: f32, amount: f32) -> (f32, f32) {
+    match curve {
+        PhaseDistortion::Off => (phase, 1.0),
+        // The first half of the cycle is read within the knee, which bends a sine towards a saw.
+        PhaseDistortion::Saw => {
+            let knee = knee(amount);
+            if phase < knee {
+                (0.5 * phase / knee, 1.0)
+            } else {
+                (0.5 + 0.5 * (phase - knee) / (1.0 - knee), 1.0)
+            }
+        }
+        // The bank is read up to 16 times a period under a falling ramp, so that the amount
+        // sweeps a resonant peak up the spectrum. The ramp is there at amount 0 too, as on the
+        // CZ, and keeps the cycle from jumping where it restarts.
+        PhaseDistortion::Resonant => ((phase * (1.0 + 15.0 * amount)).fract(), 1.0 - phase),
+    }
+}
+
+// Steepest rate at which `warp` reads the bank, relative to the unwarped phase.
+pub(crate) fn max_slope(curve: PhaseDistortion, amount: f32) -> f32 {
+    match curve {
+        PhaseDistortion::Off => 1.0,
+        PhaseDistortion::Saw => 0.5 / knee(amount),
+        PhaseDistortion::Resonant => 1.0 + 15.0 * amount,
+    }
+}
@@ -1,20 +1,130 @@
-use nih_plug::prelude::Editor;
+use nih_plug::prelude::{Editor, Param};
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::*;
-use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
+use nih_plug_vizia::{assets, create_vizia_editor, vizia, ViziaState, ViziaTheming};
+use std::array;
 use std::sync::Arc;
 
-use crate::{SeriessynthParams, HARMONICS_COUNT};
+use crate::patterns::{self, PanPattern, PhasePattern};
+use crate::{odd_even_gain, tilt_coefficient, SeriessynthParams, HARMONICS_COUNT};
+
+// Which per-harmonic parameter the harmonic rows are showing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HarmonicPage {
+    Amplitude,
+    AmplitudeB,
+    Phase,
+    Ratio,
+    Bandwidth,
+    Attack,
+    Decay,
+    Pan,
+    Layer,
+}
+
+impl vizia::prelude::Data for HarmonicPage {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+enum EditorEvent {
+    ShowPage(HarmonicPage),
+    ApplyPhasePattern(PhasePattern),
+    ApplyPanPattern(PanPattern),
+    // Copies the amplitudes of snapshot A over B, or of B over A.
+    CopySnapshot { to_b: bool },
+}
 
 #[derive(Lens)]
 struct Data {
     params: Arc<SeriessynthParams>,
+    page: HarmonicPage,
+}
+
+impl Model for Data {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|editor_event, _| match editor_event {
+            EditorEvent::ShowPage(page) => self.page = *page,
+            EditorEvent::ApplyPhasePattern(pattern) => {
+                let active_harmonics = self.params.active_harmonics.value() as usize;
+                let tilt = self.params.tilt.value();
+                let odd_even = self.params.odd_even.value();
+                let morph = self.params.morph.value();
+                let amplitudes = array::from_fn(|i| {
+                    if i < active_harmonics {
+                        let harmonic = &self.params.harmonics[i];
+                        let (a, b) = (harmonic.nope.value(), harmonic.nope_b.value());
+                        tilt_coefficient(i, tilt) * odd_even_gain(i + 1, odd_even) * (a + (b - a) * morph)
+                    } else {
+                        0.0
+                    }
+                });
+                let phases = patterns::phases(*pattern, &amplitudes);
+                for (harmonic, phase) in self.params.harmonics.iter().zip(phases) {
+                    cx.emit(ParamEvent::BeginSetParameter(&harmonic.phase).upcast());
+                    cx.emit(ParamEvent::SetParameter(&harmonic.phase, phase).upcast());
+                    cx.emit(ParamEvent::EndSetParameter(&harmonic.phase).upcast());
+                }
+            }
+            EditorEvent::ApplyPanPattern(pattern) => {
+                let pans = patterns::pans(*pattern, self.params.active_harmonics.value() as usize);
+                for (harmonic, pan) in self.params.harmonics.iter().zip(pans) {
+                    cx.emit(ParamEvent::BeginSetParameter(&harmonic.pan).upcast());
+                    cx.emit(ParamEvent::SetParameter(&harmonic.pan, pan).upcast());
+                    cx.emit(ParamEvent::EndSetParameter(&harmonic.pan).upcast());
+                }
+            }
+            EditorEvent::CopySnapshot { to_b } => {
+                for harmonic in self.params.harmonics.iter() {
+                    let (from, to) = if *to_b { (&harmonic.nope, &harmonic.nope_b) } else { (&harmonic.nope_b, &harmonic.nope) };
+                    cx.emit(ParamEvent::BeginSetParameter(to).upcast());
+                    cx.emit(ParamEvent::SetParameter(to, from.value()).upcast());
+                    cx.emit(ParamEvent::EndSetParameter(to).upcast());
+                }
+            }
+        });
+    }
+}
+
+fn page_button(cx: &mut Context, label: &'static str, page: HarmonicPage) {
+    Button::new(
+        cx,
+        move |cx| cx.emit(EditorEvent::ShowPage(page)),
+        |cx| Label::new(cx, label),
+    )
+    .width(Stretch(1.0));
+}
+
+fn phase_pattern_button(cx: &mut Context, label: &'static str, pattern: PhasePattern) {
+    Button::new(
+        cx,
+        move |cx| cx.emit(EditorEvent::ApplyPhasePattern(pattern)),
+        |cx| Label::new(cx, label),
+    )
+    .width(Stretch(1.0));
+}
+
+fn pan_pattern_button(cx: &mut Context, label: &'static str, pattern: PanPattern) {
+    Button::new(
+        cx,
+        move |cx| cx.emit(EditorEvent::ApplyPanPattern(pattern)),
+        |cx| Label::new(cx, label),
+    )
+    .width(Stretch(1.0));
 }
 
-impl Model for Data {}
+fn copy_snapshot_button(cx: &mut Context, label: &'static str, to_b: bool) {
+    Button::new(
+        cx,
+        move |cx| cx.emit(EditorEvent::CopySnapshot { to_b }),
+        |cx| Label::new(cx, label),
+    )
+    .width(Stretch(1.0));
+}
 
 pub(crate) fn default_state() -> Arc<ViziaState> {
-    ViziaState::new(|| (750, 850))
+    ViziaState::new(|| (1750, 850))
 }
 
 pub(crate) fn create(
@@ -26,6 +136,7 @@ pub(crate) fn create(
 
         Data {
             params: params.clone(),
+            page: HarmonicPage::Amplitude,
         }
         .build(cx);
 
@@ -35,35 +146,104 @@ pub(crate) fn create(
                 .font_weight(FontWeightKeyword::Light)
                 .font_size(30.0)
                 .height(Pixels(50.0))
-                .width(Pixels(600.0))
+                .width(Pixels(1600.0))
                 .child_top(Stretch(1.0))
                 .child_bottom(Pixels(10.0))
                 .text_align(TextAlign::Center);
 
             HStack::new(cx, |cx| {
                 VStack::new(cx, |cx| {
-                    for i in 0..HARMONICS_COUNT {
-                        HStack::new(cx, |cx| {
-                            let index = i;
-                            let label_text = format!("{:02}倍音", i + 1);
-                            Label::new(cx, &label_text)
-                                .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
-                                .font_weight(FontWeightKeyword::Light)
-                                .font_size(20.0)
-                                .height(Pixels(20.0))
-                                .child_top(Stretch(1.0))
-                                .child_bottom(Pixels(0.0));
-                            ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].nope)
-                                .height(Pixels(25.0))
-                                .width(Stretch(1.0));
+                    HStack::new(cx, |cx| {
+                        page_button(cx, "振幅", HarmonicPage::Amplitude);
+                        page_button(cx, "振幅B", HarmonicPage::AmplitudeB);
+                        page_button(cx, "位相", HarmonicPage::Phase);
+                        page_button(cx, "比率", HarmonicPage::Ratio);
+                        page_button(cx, "レイヤー", HarmonicPage::Layer);
+                    })
+                    .height(Pixels(30.0))
+                    .col_between(Pixels(5.0));
+                    HStack::new(cx, |cx| {
+                        page_button(cx, "ノイズ", HarmonicPage::Bandwidth);
+                        page_button(cx, "アタック", HarmonicPage::Attack);
+                        page_button(cx, "ディケイ", HarmonicPage::Decay);
+                        page_button(cx, "パン", HarmonicPage::Pan);
+                    })
+                    .height(Pixels(30.0))
+                    .col_between(Pixels(5.0));
+
+                    Binding::new(cx, Data::page, |cx, page| {
+                        let page = page.get(cx);
+                        if page == HarmonicPage::Phase {
+                            HStack::new(cx, |cx| {
+                                phase_pattern_button(cx, "Zero", PhasePattern::Zero);
+                                phase_pattern_button(cx, "Alt", PhasePattern::Alternating);
+                                phase_pattern_button(cx, "Random", PhasePattern::Random);
+                                phase_pattern_button(cx, "Schroeder", PhasePattern::Schroeder);
+                            })
+                            .height(Pixels(30.0))
+                            .col_between(Pixels(5.0));
+                        }
+                        if page == HarmonicPage::Pan {
+                            HStack::new(cx, |cx| {
+                                pan_pattern_button(cx, "Center", PanPattern::Center);
+                                pan_pattern_button(cx, "Odd/Even", PanPattern::OddEven);
+                                pan_pattern_button(cx, "Spread", PanPattern::Spread);
+                                pan_pattern_button(cx, "Random", PanPattern::Random);
+                            })
+                            .height(Pixels(30.0))
+                            .col_between(Pixels(5.0));
+                        }
+                        if page == HarmonicPage::Amplitude || page == HarmonicPage::AmplitudeB {
+                            HStack::new(cx, |cx| {
+                                copy_snapshot_button(cx, "A → B", true);
+                                copy_snapshot_button(cx, "B → A", false);
+                            })
+                            .height(Pixels(30.0))
+                            .col_between(Pixels(5.0));
+                        }
+
+                        ScrollView::new(cx, 0.0, 0.0, false, true, move |cx| {
+                            VStack::new(cx, |cx| {
+                                for i in 0..HARMONICS_COUNT {
+                                    HStack::new(cx, |cx| {
+                                        let index = i;
+                                        let label_text = format!("{:02}倍音", i + 1);
+                                        Label::new(cx, &label_text)
+                                            .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                                            .font_weight(FontWeightKeyword::Light)
+                                            .font_size(20.0)
+                                            .height(Pixels(20.0))
+                                            .child_top(Stretch(1.0))
+                                            .child_bottom(Pixels(0.0));
+                                        match page {
+                                            HarmonicPage::Amplitude => ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].nope),
+                                            HarmonicPage::AmplitudeB => ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].nope_b),
+                                            HarmonicPage::Phase => ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].phase),
+                                            HarmonicPage::Ratio => ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].ratio),
+                                            HarmonicPage::Bandwidth => ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].bandwidth),
+                                            HarmonicPage::Attack => ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].attack),
+                                            HarmonicPage::Decay => ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].decay),
+                                            HarmonicPage::Pan => ParamSlider::new(cx, Data::params, move |params| &params.harmonics[index].pan),
+                                            HarmonicPage::Layer => ParamSlider::new(cx, Data::params, move |params| &params.layer[index].amplitude),
+                                        }
+                                        .height(Pixels(25.0))
+                                        .width(Stretch(1.0));
+                                    })
+                                    .width(Pixels(300.0));
+                                }
+                            })
+                            .row_between(Pixels(5.0))
+                            .width(Pixels(320.0))
+                            .child_left(Stretch(1.0))
+                            .child_right(Stretch(1.0));
                         })
-                        .width(Pixels(300.0));
-                    }
+                        .width(Pixels(340.0))
+                        .height(Stretch(1.0));
+                    });
                 })
                 .row_between(Pixels(5.0))
-                .width(Pixels(320.0))
-                .child_left(Stretch(1.0))
-                .child_right(Stretch(1.0));
+                .width(Pixels(340.0))
+                .height(Pixels(780.0));
 
                 VStack::new(cx, |cx| {
                     Label::new(cx, "Gain")
@@ -120,7 +300,16 @@ pub(crate) fn create(
                         .child_bottom(Pixels(0.0));
                     ParamSlider::new(cx, Data::params, |params| &params.release);
 
-                    Label::new(cx, &format!("{}倍音より上の波形", HARMONICS_COUNT))
+                    Label::new(cx, "倍音数")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.active_harmonics);
+
+                    Label::new(cx, "倍音数より上の波形")
                         .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
                         .font_weight(FontWeightKeyword::Light)
                         .font_size(20.0)
@@ -136,7 +325,16 @@ pub(crate) fn create(
                         .height(Pixels(25.0))
                         .child_top(Stretch(1.0))
                         .child_bottom(Pixels(0.0));
-                    ParamSlider::new(cx, Data::params, |params| &params.amp_width);
+                    ParamSlider::new(cx, Data::params, |params| &params.tilt);
+
+                    Label::new(cx, "Odd/Even")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.odd_even);
 
                     Label::new(cx, "Noise")
                         .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
@@ -180,6 +378,87 @@ pub(crate) fn create(
                 .child_right(Stretch(1.0));
 
                 VStack::new(cx, |cx| {
+                    Label::new(cx, "Oscillator")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.osc_mode);
+
+                    Label::new(cx, "Nyquist roll-off")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.nyquist_rolloff);
+
+                    Label::new(cx, "Inharmonicity")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.inharmonicity);
+
+                    Label::new(cx, "Stretch")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.stretch);
+
+                    Label::new(cx, "Attack curve")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.env_attack_curve);
+
+                    Label::new(cx, "Decay curve")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.env_decay_curve);
+
+                    Label::new(cx, "Decay tilt")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.decay_tilt);
+
+                    Label::new(cx, "Morph")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.morph);
+
+                    Label::new(cx, "Velocity morph")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.velocity_morph);
+
                     Label::new(cx, "LFO Freq")
                         .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
                         .font_weight(FontWeightKeyword::Light)
@@ -211,6 +490,576 @@ pub(crate) fn create(
                 .width(Pixels(200.0))
                 .child_left(Stretch(1.0))
                 .child_right(Stretch(1.0));
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "Spectral filter")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.spectral_filter);
+
+                    Label::new(cx, "Cutoff")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.spectral_cutoff);
+
+                    Label::new(cx, "Resonance")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.spectral_resonance);
+
+                    Label::new(cx, "Keytrack")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.spectral_keytrack);
+
+                    Label::new(cx, "Env amount")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.spectral_env_amount);
+
+                    Label::new(cx, "Vowel")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.vowel);
+
+                    Label::new(cx, "Formant")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.formant_amount);
+
+                    Label::new(cx, "Noise color")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.noise_color);
+
+                    Label::new(cx, "Noise attack")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.noise_attack);
+
+                    Label::new(cx, "Noise decay")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.noise_decay);
+
+                    Label::new(cx, "Noise sustain")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.noise_sustain);
+                })
+                .row_between(Pixels(0.0))
+                .width(Pixels(200.0))
+                .child_left(Stretch(1.0))
+                .child_right(Stretch(1.0));
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "Filter")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_mode);
+
+                    Label::new(cx, "Filter slope")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_slope);
+
+                    Label::new(cx, "Filter cutoff")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_cutoff);
+
+                    Label::new(cx, "Filter resonance")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_resonance);
+
+                    Label::new(cx, "Filter keytrack")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_keytrack);
+
+                    Label::new(cx, "Filter velocity")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_velocity);
+
+                    Label::new(cx, "Filter env amount")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_env_amount);
+
+                    Label::new(cx, "Filter attack")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_attack);
+
+                    Label::new(cx, "Filter decay")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_decay);
+
+                    Label::new(cx, "Filter sustain")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_sustain);
+
+                    Label::new(cx, "Filter release")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.filter_release);
+
+                    Label::new(cx, "Seed")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.seed);
+
+                    Label::new(cx, "Deterministic")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.deterministic);
+                })
+                .row_between(Pixels(0.0))
+                .width(Pixels(200.0))
+                .child_left(Stretch(1.0))
+                .child_right(Stretch(1.0));
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "Unison")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.unison);
+
+                    Label::new(cx, "Detune")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.detune);
+
+                    Label::new(cx, "Spread")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.spread);
+
+                    Label::new(cx, "Unison phase")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.unison_phase);
+
+                    Label::new(cx, "Key pan")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.key_pan);
+
+                    Label::new(cx, "Width")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.width);
+
+                    Label::new(cx, "Layer factor")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.layer_freq_factor);
+
+                    Label::new(cx, "Layer inverse factor")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.layer_freq_inverse_factor);
+
+                    Label::new(cx, "Layer cent")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.layer_cent);
+
+                    Label::new(cx, "Layer mix")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.layer_mix);
+
+                    Label::new(cx, "Sub level")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.sub_level);
+
+                    Label::new(cx, "Sub octave")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.sub_octave);
+                })
+                .row_between(Pixels(0.0))
+                .width(Pixels(200.0))
+                .child_left(Stretch(1.0))
+                .child_right(Stretch(1.0));
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "FM source")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.fm_source);
+
+                    Label::new(cx, "FM ratio")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.fm_ratio);
+
+                    Label::new(cx, "FM index")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.fm_index);
+
+                    Label::new(cx, "FM feedback")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.fm_feedback);
+
+                    Label::new(cx, "FM attack")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.fm_attack);
+
+                    Label::new(cx, "FM decay")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.fm_decay);
+
+                    Label::new(cx, "FM sustain")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.fm_sustain);
+
+                    Label::new(cx, "FM release")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.fm_release);
+
+                    Label::new(cx, "Ring mod")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.ring_mode);
+
+                    Label::new(cx, "Ring tracking")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.ring_tracking);
+
+                    Label::new(cx, "Ring factor")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.ring_freq_factor);
+
+                    Label::new(cx, "Ring inverse factor")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.ring_freq_inverse_factor);
+
+                    Label::new(cx, "Ring freq")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.ring_freq);
+
+                    Label::new(cx, "Ring mix")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.ring_mix);
+                })
+                .row_between(Pixels(0.0))
+                .width(Pixels(200.0))
+                .child_left(Stretch(1.0))
+                .child_right(Stretch(1.0));
+
+                VStack::new(cx, |cx| {
+                    Label::new(cx, "Phase distortion")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.phase_distortion);
+
+                    Label::new(cx, "PD amount")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.pd_amount);
+
+                    Label::new(cx, "PD attack")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.pd_attack);
+
+                    Label::new(cx, "PD decay")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.pd_decay);
+
+                    Label::new(cx, "PD sustain")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.pd_sustain);
+
+                    Label::new(cx, "PD release")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.pd_release);
+
+                    Label::new(cx, "Velocity curve")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.velocity_curve);
+
+                    Label::new(cx, "Velocity depth")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.velocity_depth);
+
+                    Label::new(cx, "Velocity brightness")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.velocity_brightness);
+
+                    Label::new(cx, "Pressure depth")
+                        .font_family(vec![FamilyOwned::Name(String::from(assets::NOTO_SANS))])
+                        .font_weight(FontWeightKeyword::Light)
+                        .font_size(20.0)
+                        .height(Pixels(25.0))
+                        .child_top(Stretch(1.0))
+                        .child_bottom(Pixels(0.0));
+                    ParamSlider::new(cx, Data::params, |params| &params.pressure_depth);
+                })
+                .row_between(Pixels(0.0))
+                .width(Pixels(200.0))
+                .child_left(Stretch(1.0))
+                .child_right(Stretch(1.0));
             });
         });
 
@@ -1,23 +1,48 @@
 use nih_plug::prelude::*;
 use nih_plug::params::enums::Enum;
+use nih_plug::wrapper::state::ParamValue;
 use nih_plug_vizia::ViziaState;
 use std::array;
 use std::f32::{consts, EPSILON};
 use std::fmt::Debug;
 use std::sync::Arc;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, VecDeque};
 
+mod bandlimit;
+mod distortion;
 mod editor;
+mod noise;
+mod oscillator;
+mod patterns;
+mod spectral;
+mod svf;
 
-const HARMONICS_COUNT: usize = 31;
+use bandlimit::BandLimit;
+use distortion::PhaseDistortion;
+use noise::{Noise, NoiseColor, Rng};
+use spectral::{Formants, SpectralFilter, SpectralFilterMode};
+use svf::{FilterMode, FilterSlope, Svf, SvfCoefficients};
+
+const HARMONICS_COUNT: usize = 128;
+// Projects saved before the harmonic count became a parameter rendered exactly this many.
+const DEFAULT_ACTIVE_HARMONICS: usize = 31;
+const MAX_BLOCK_SIZE: usize = 64;
+const MAX_UNISON: usize = 8;
+// Samples between updates of the spectral filter and formant gains.
+const SPECTRAL_INTERVAL: usize = 16;
 
 pub struct Seriessynth {
     params: Arc<SeriessynthParams>,
     sample_rate: f32,
-    voices: HashMap<u8, VecDeque<Voice>>,
+    // Ordered so that the voices are always summed in the same order.
+    voices: BTreeMap<u8, VecDeque<Voice>>,
     lfo_phase: f32,
+    block: Block,
+    // The one source of randomness on the audio thread. Seeds the noise of every new voice.
+    rng: Rng,
 }
 
+#[derive(Clone, Copy)]
 enum AHDSR {
     A,
     H,
@@ -27,7 +52,7 @@ enum AHDSR {
     DEAD,
 }
 
-#[derive(Debug, PartialEq, Enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
 enum Waveform {
     None,
     Triangle,
@@ -36,53 +61,103 @@ enum Waveform {
 }
 
 #[derive(Debug, PartialEq, Enum)]
-enum LfoDest {
-    None,
-    Phase,
-    Gain,
+enum OscMode {
+    Exact,
+    Fast,
 }
 
-#[derive(Debug, PartialEq)]
-enum AmpWidth {
-    One,
-    N,
-    N2,
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+enum FmSource {
+    Off,
+    Sine,
+    // The second layer's harmonic spectrum.
+    Layer,
 }
 
-impl Enum for AmpWidth {
-    fn variants() -> &'static [&'static str] {
-        &["1", "1/N", "1/N^2"]
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+enum RingMode {
+    Off,
+    Ring,
+    AM,
+}
 
-    fn ids() -> Option<&'static [&'static str]> {
-        Some(&["1", "N", "N2"])
-    }
-    fn to_index(self) -> usize {
-        match self {
-            AmpWidth::One => 0,
-            AmpWidth::N => 1,
-            AmpWidth::N2 => 2,
-        }
-    }
-    fn from_index(index: usize) -> Self {
-        match index {
-            0 => AmpWidth::One,
-            1 => AmpWidth::N,
-            2 => AmpWidth::N2,
-            _ => panic!("Invalid index"),
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+enum RingTracking {
+    Note,
+    Fixed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+enum VelocityCurve {
+    Linear,
+    Exponential,
+    Fixed,
 }
 
+#[derive(Debug, PartialEq, Enum)]
+enum LfoDest {
+    None,
+    Phase,
+    Gain,
+    Morph,
+    Balance,
+    Vowel,
+}
 
 struct Voice {
-    phase: f32,
+    // One per unison copy, all sharing the envelopes below.
+    oscillators: [Oscillator; MAX_UNISON],
+    layer_phase: f32,
+    sub_phase: f32,
+    fm_phase: f32,
+    // The modulator's last two outputs, averaged for the feedback.
+    fm_output: [f32; 2],
+    fm_envelope: EnvelopeState,
+    ring_phase: f32,
+    pd_envelope: EnvelopeState,
     midi_note_freq: f32,
+    // Poly pressure, full until the first pressure event.
     midi_note_gain: Smoother<f32>,
+    velocity: f32,
+    // Distance from middle C for the key pan, -1 and 1 two octaves away.
+    key: f32,
+    envelope: EnvelopeState,
+    // Two stages for each of the left and right channels.
+    filter: [[Svf; 2]; 2],
+    filter_envelope: EnvelopeState,
+    noise: Noise,
+    // Lowpassed noise that modulates the amplitude of each bandwidth enhanced partial.
+    partial_noise: [f32; HARMONICS_COUNT],
+    // Shapes the noise on top of the master envelope and has no release of its own.
+    noise_envelope: EnvelopeState,
+    // Per-partial envelopes, only stepped while some partial's times differ from the master's.
+    partial_envelopes: [EnvelopeState; HARMONICS_COUNT],
+    uniform_envelope: bool,
+    clock: DecayClock,
+    dead: f32,
+}
+
+struct Oscillator {
+    phase: f32,
+    // Per-partial phases, only advanced while the partials are not integer multiples of `phase`.
+    partial_phases: [f32; HARMONICS_COUNT],
+    harmonic: bool,
+}
+
+// Samples spent in decay/sustain and in release, and the master level on release, from which
+// the decay tilt works out the level of every partial of the tail.
+#[derive(Clone, Copy)]
+struct DecayClock {
+    decay_samples: f32,
+    release_samples: f32,
+    release_level: f32,
+}
+
+#[derive(Clone, Copy)]
+struct EnvelopeState {
     ahdsr: AHDSR,
-    envelope: f32,
+    level: f32,
     hold: f32,
-    dead: f32,
 }
 
 #[derive(Params)]
@@ -114,15 +189,232 @@ struct SeriessynthParams {
     #[id = "R"]
     pub release: FloatParam,
 
+    #[id = "envattackcurve"]
+    pub env_attack_curve: FloatParam,
+
+    #[id = "envdecaycurve"]
+    pub env_decay_curve: FloatParam,
+
+    #[id = "decaytilt"]
+    pub decay_tilt: FloatParam,
+
     #[nested(array, group= "harmonics")]
     pub harmonics: [ArrayParams; HARMONICS_COUNT],
 
-    #[id = "ampwidth"]
-    pub amp_width: EnumParam<AmpWidth>,
+    // Amplitudes of the second layer's harmonics.
+    #[nested(array, group= "layer")]
+    pub layer: [LayerParams; HARMONICS_COUNT],
+
+    #[id = "layerfactor"]
+    pub layer_freq_factor: IntParam,
+
+    #[id = "layerinversefactor"]
+    pub layer_freq_inverse_factor: IntParam,
+
+    #[id = "layercent"]
+    pub layer_cent: IntParam,
+
+    #[id = "layermix"]
+    pub layer_mix: FloatParam,
+
+    #[id = "sublevel"]
+    pub sub_level: FloatParam,
+
+    #[id = "suboctave"]
+    pub sub_octave: IntParam,
+
+    #[id = "fmsource"]
+    pub fm_source: EnumParam<FmSource>,
+
+    #[id = "fmratio"]
+    pub fm_ratio: FloatParam,
+
+    #[id = "fmindex"]
+    pub fm_index: FloatParam,
+
+    #[id = "fmfeedback"]
+    pub fm_feedback: FloatParam,
+
+    #[id = "fmA"]
+    pub fm_attack: FloatParam,
+
+    #[id = "fmD"]
+    pub fm_decay: FloatParam,
+
+    #[id = "fmS"]
+    pub fm_sustain: FloatParam,
+
+    #[id = "fmR"]
+    pub fm_release: FloatParam,
+
+    #[id = "ringmode"]
+    pub ring_mode: EnumParam<RingMode>,
+
+    #[id = "ringtracking"]
+    pub ring_tracking: EnumParam<RingTracking>,
+
+    #[id = "ringfactor"]
+    pub ring_freq_factor: IntParam,
+
+    #[id = "ringinversefactor"]
+    pub ring_freq_inverse_factor: IntParam,
+
+    #[id = "ringfreq"]
+    pub ring_freq: FloatParam,
+
+    #[id = "ringmix"]
+    pub ring_mix: FloatParam,
+
+    #[id = "phasedistortion"]
+    pub phase_distortion: EnumParam<PhaseDistortion>,
+
+    #[id = "pdamount"]
+    pub pd_amount: FloatParam,
+
+    #[id = "pdA"]
+    pub pd_attack: FloatParam,
+
+    #[id = "pdD"]
+    pub pd_decay: FloatParam,
+
+    #[id = "pdS"]
+    pub pd_sustain: FloatParam,
+
+    #[id = "pdR"]
+    pub pd_release: FloatParam,
+
+    #[id = "morph"]
+    pub morph: FloatParam,
+
+    #[id = "velocitymorph"]
+    pub velocity_morph: FloatParam,
+
+    #[id = "velocitycurve"]
+    pub velocity_curve: EnumParam<VelocityCurve>,
+
+    #[id = "velocitydepth"]
+    pub velocity_depth: FloatParam,
+
+    #[id = "velocitybrightness"]
+    pub velocity_brightness: FloatParam,
+
+    #[id = "pressuredepth"]
+    pub pressure_depth: FloatParam,
+
+    #[id = "unison"]
+    pub unison: IntParam,
+
+    #[id = "detune"]
+    pub detune: FloatParam,
+
+    #[id = "spread"]
+    pub spread: FloatParam,
+
+    #[id = "unisonphase"]
+    pub unison_phase: FloatParam,
+
+    #[id = "keypan"]
+    pub key_pan: FloatParam,
+
+    #[id = "width"]
+    pub width: FloatParam,
+
+    #[id = "activeharmonics"]
+    pub active_harmonics: IntParam,
+
+    #[id = "inharmonicity"]
+    pub inharmonicity: FloatParam,
+
+    #[id = "stretch"]
+    pub stretch: FloatParam,
+
+    #[id = "tilt"]
+    pub tilt: FloatParam,
+
+    #[id = "oddeven"]
+    pub odd_even: FloatParam,
+
+    #[id = "spectralfilter"]
+    pub spectral_filter: EnumParam<SpectralFilterMode>,
+
+    #[id = "spectralcutoff"]
+    pub spectral_cutoff: FloatParam,
+
+    #[id = "spectralresonance"]
+    pub spectral_resonance: FloatParam,
+
+    #[id = "spectralkeytrack"]
+    pub spectral_keytrack: FloatParam,
+
+    #[id = "spectralenv"]
+    pub spectral_env_amount: FloatParam,
+
+    #[id = "vowel"]
+    pub vowel: FloatParam,
+
+    #[id = "formant"]
+    pub formant_amount: FloatParam,
+
+    #[id = "filtermode"]
+    pub filter_mode: EnumParam<FilterMode>,
+
+    #[id = "filterslope"]
+    pub filter_slope: EnumParam<FilterSlope>,
+
+    #[id = "filtercutoff"]
+    pub filter_cutoff: FloatParam,
+
+    #[id = "filterresonance"]
+    pub filter_resonance: FloatParam,
+
+    #[id = "filterkeytrack"]
+    pub filter_keytrack: FloatParam,
+
+    #[id = "filterenv"]
+    pub filter_env_amount: FloatParam,
+
+    #[id = "filtervelocity"]
+    pub filter_velocity: FloatParam,
+
+    #[id = "filterA"]
+    pub filter_attack: FloatParam,
+
+    #[id = "filterD"]
+    pub filter_decay: FloatParam,
+
+    #[id = "filterS"]
+    pub filter_sustain: FloatParam,
+
+    #[id = "filterR"]
+    pub filter_release: FloatParam,
+
+    #[id = "oscmode"]
+    pub osc_mode: EnumParam<OscMode>,
+
+    #[id = "rolloff"]
+    pub nyquist_rolloff: FloatParam,
 
     #[id = "noise"]
     pub noise: FloatParam,
 
+    #[id = "noisecolor"]
+    pub noise_color: EnumParam<NoiseColor>,
+
+    #[id = "noiseA"]
+    pub noise_attack: FloatParam,
+
+    #[id = "noiseD"]
+    pub noise_decay: FloatParam,
+
+    #[id = "noiseS"]
+    pub noise_sustain: FloatParam,
+
+    #[id = "seed"]
+    pub seed: IntParam,
+
+    #[id = "deterministic"]
+    pub deterministic: BoolParam,
+
     #[id = "Base Freq factor"]
     pub base_freq_factor: IntParam,
 
@@ -146,6 +438,34 @@ struct SeriessynthParams {
 struct ArrayParams {
     #[id = "noope"]
     pub nope: FloatParam,
+
+    // Snapshot B of the amplitudes, `nope` being snapshot A.
+    #[id = "noopeb"]
+    pub nope_b: FloatParam,
+
+    #[id = "phase"]
+    pub phase: FloatParam,
+
+    #[id = "ratio"]
+    pub ratio: FloatParam,
+
+    #[id = "bandwidth"]
+    pub bandwidth: FloatParam,
+
+    #[id = "attack"]
+    pub attack: FloatParam,
+
+    #[id = "decay"]
+    pub decay: FloatParam,
+
+    #[id = "pan"]
+    pub pan: FloatParam,
+}
+
+#[derive(Params)]
+struct LayerParams {
+    #[id = "layer"]
+    pub amplitude: FloatParam,
 }
 
 impl Default for Seriessynth {
@@ -153,8 +473,10 @@ impl Default for Seriessynth {
         Self {
             params: Arc::new(SeriessynthParams::default()),
             sample_rate: 96000.0,
-            voices: HashMap::new(),
+            voices: BTreeMap::new(),
             lfo_phase: 0.0,
+            block: Block::default(),
+            rng: Rng::new(rand::random()),
         }
     }
 }
@@ -212,237 +534,1724 @@ impl Default for SeriessynthParams {
                 0.0,
                 FloatRange::Linear { min: 0.0, max: 1.0 },
             ),
+            // Partial n's attack time is scaled by n^(attack curve), its decay and release by n^(decay curve).
+            env_attack_curve: FloatParam::new(
+                "Attack curve",
+                0.0,
+                FloatRange::Linear {
+                    min: -2.0,
+                    max: 2.0,
+                },
+            )
+            .with_step_size(0.01),
+            env_decay_curve: FloatParam::new(
+                "Decay curve",
+                0.0,
+                FloatRange::Linear {
+                    min: -2.0,
+                    max: 2.0,
+                },
+            )
+            .with_step_size(0.01),
+            // Partial n decays and releases tilt^(n - 1) times faster than the fundamental.
+            decay_tilt: FloatParam::new(
+                "Decay tilt",
+                1.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 2.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit("x")
+            .with_value_to_string(formatters::v2s_f32_rounded(3)),
             harmonics: array::from_fn(|i| {
-                if i == 0 {
-                    ArrayParams {
-                        nope: FloatParam::new(
-                            "1倍音",
-                            1.0,
-                            FloatRange::Linear {
-                                min: -1.0,
-                                max: 1.0,
-                            },
-                        )
-                        .with_smoother(SmoothingStyle::Linear(10.0)),
-                    }
-                } else {
-                    ArrayParams {
-                        nope: FloatParam::new(
-                            &format!("{:02}倍音", i + 1),
-                            0.0,
-                            FloatRange::Linear {
-                                min: -1.0,
-                                max: 1.0,
-                            },
-                        )
-                        .with_smoother(SmoothingStyle::Linear(10.0)),
-                    }
+                let name = if i == 0 { String::from("1倍音") } else { format!("{:02}倍音", i + 1) };
+                ArrayParams {
+                    nope: FloatParam::new(
+                        &name,
+                        if i == 0 { 1.0 } else { 0.0 },
+                        FloatRange::Linear {
+                            min: -1.0,
+                            max: 1.0,
+                        },
+                    )
+                    .with_smoother(SmoothingStyle::Linear(10.0)),
+                    nope_b: FloatParam::new(
+                        format!("{} B", name),
+                        if i == 0 { 1.0 } else { 0.0 },
+                        FloatRange::Linear {
+                            min: -1.0,
+                            max: 1.0,
+                        },
+                    )
+                    .with_smoother(SmoothingStyle::Linear(10.0)),
+                    phase: FloatParam::new(
+                        format!("{} 位相", name),
+                        0.0,
+                        FloatRange::Linear {
+                            min: 0.0,
+                            max: 360.0,
+                        },
+                    )
+                    .with_smoother(SmoothingStyle::Linear(10.0))
+                    .with_unit("°")
+                    .with_value_to_string(formatters::v2s_f32_rounded(0)),
+                    // Zero follows the inharmonicity and stretch settings.
+                    ratio: FloatParam::new(
+                        format!("{} 比率", name),
+                        0.0,
+                        FloatRange::Linear {
+                            min: 0.0,
+                            max: 64.0,
+                        },
+                    )
+                    .with_step_size(0.001)
+                    .with_value_to_string(Arc::new(|value| {
+                        if value <= 0.0 { String::from("Auto") } else { format!("{:.3}", value) }
+                    }))
+                    .with_string_to_value(Arc::new(|string| {
+                        if string.trim().eq_ignore_ascii_case("auto") { Some(0.0) } else { string.trim().parse().ok() }
+                    })),
+                    // Share of the partial's energy spread into noise around its frequency.
+                    bandwidth: FloatParam::new(
+                        format!("{} ノイズ", name),
+                        0.0,
+                        FloatRange::Linear {
+                            min: 0.0,
+                            max: 1.0,
+                        },
+                    )
+                    .with_unit("%")
+                    .with_value_to_string(formatters::v2s_f32_percentage(0))
+                    .with_string_to_value(formatters::s2v_f32_percentage()),
+                    // Scale this partial's attack, and its decay and release, on top of the curves.
+                    attack: FloatParam::new(
+                        format!("{} アタック", name),
+                        1.0,
+                        FloatRange::Skewed {
+                            min: 0.1,
+                            max: 10.0,
+                            factor: FloatRange::skew_factor(-2.0),
+                        },
+                    )
+                    .with_unit("x")
+                    .with_value_to_string(formatters::v2s_f32_rounded(2)),
+                    decay: FloatParam::new(
+                        format!("{} ディケイ", name),
+                        1.0,
+                        FloatRange::Skewed {
+                            min: 0.1,
+                            max: 10.0,
+                            factor: FloatRange::skew_factor(-2.0),
+                        },
+                    )
+                    .with_unit("x")
+                    .with_value_to_string(formatters::v2s_f32_rounded(2)),
+                    pan: FloatParam::new(
+                        format!("{} パン", name),
+                        0.0,
+                        FloatRange::Linear {
+                            min: -1.0,
+                            max: 1.0,
+                        },
+                    )
+                    .with_value_to_string(formatters::v2s_f32_panning())
+                    .with_string_to_value(formatters::s2v_f32_panning()),
                 }
             }),
-            amp_width: EnumParam::new("倍音係数", AmpWidth::One),
-            noise: FloatParam::new(
-                "Noise",
+            layer: array::from_fn(|i| {
+                let name = if i == 0 { String::from("1倍音") } else { format!("{:02}倍音", i + 1) };
+                LayerParams {
+                    amplitude: FloatParam::new(
+                        format!("{} レイヤー", name),
+                        if i == 0 { 1.0 } else { 0.0 },
+                        FloatRange::Linear {
+                            min: -1.0,
+                            max: 1.0,
+                        },
+                    )
+                    .with_smoother(SmoothingStyle::Linear(10.0)),
+                }
+            }),
+            // The layer's fundamental relative to the note, independent of the base frequency.
+            layer_freq_factor: IntParam::new(
+                "Layer factor",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: 23,
+                },
+            ),
+            layer_freq_inverse_factor: IntParam::new(
+                "Layer inverse factor",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: 23,
+                },
+            ),
+            layer_cent: IntParam::new(
+                "Layer cent",
+                0,
+                IntRange::Linear {
+                    min: -100,
+                    max: 100,
+                },
+            ),
+            // Crossfades from the main oscillator alone to the layer alone, both at full level
+            // halfway.
+            layer_mix: FloatParam::new(
+                "Layer mix",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // A sine one or two octaves below the main oscillator.
+            sub_level: FloatParam::new(
+                "Sub level",
                 0.0,
                 FloatRange::Linear {
                     min: 0.0,
                     max: 1.0,
                 },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            sub_octave: IntParam::new(
+                "Sub octave",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: 2,
+                },
             ),
-            base_freq_factor: IntParam::new(
-                "Base Freq factor",
+            // Phase modulation of the main oscillator by a modulator at `fm_ratio` times its
+            // frequency. The index is the peak phase deviation in radians.
+            fm_source: EnumParam::new("FM source", FmSource::Off),
+            fm_ratio: FloatParam::new(
+                "FM ratio",
+                1.0,
+                FloatRange::Skewed {
+                    min: 0.125,
+                    max: 16.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_step_size(0.001),
+            fm_index: FloatParam::new(
+                "FM index",
+                0.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 20.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            // Feeds the modulator's output back into its own phase, up to π radians.
+            fm_feedback: FloatParam::new(
+                "FM feedback",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            fm_attack: FloatParam::new(
+                "FM attack",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            fm_decay: FloatParam::new(
+                "FM decay",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            fm_sustain: FloatParam::new(
+                "FM sustain",
+                1.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            fm_release: FloatParam::new(
+                "FM release",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            // Multiplies the main oscillator by a sine, bipolar for ring and unipolar for AM. It
+            // runs at the note frequency times the factors, or at `ring_freq`.
+            ring_mode: EnumParam::new("Ring mod", RingMode::Off),
+            ring_tracking: EnumParam::new("Ring tracking", RingTracking::Note),
+            ring_freq_factor: IntParam::new(
+                "Ring factor",
                 1,
                 IntRange::Linear {
                     min: 1,
                     max: 23,
                 },
             ),
-            base_freq_inverse_factor: IntParam::new(
-                "Base Freq inverse factor",
+            ring_freq_inverse_factor: IntParam::new(
+                "Ring inverse factor",
                 1,
                 IntRange::Linear {
                     min: 1,
                     max: 23,
                 },
             ),
-            plus_n_cent: IntParam::new(
-                "+ N Cent",
-                0,
-                IntRange::Linear {
-                    min: -100,
-                    max: 100,
+            ring_freq: FloatParam::new(
+                "Ring freq",
+                100.0,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 5_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(10.0))
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            ring_mix: FloatParam::new(
+                "Ring mix",
+                1.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // Warps the phase the harmonic bank is read at, by the amount times its envelope.
+            // Inharmonic partials are left alone. Saw at amount 0 is the plain bank, Resonant at
+            // amount 0 still fades each cycle out.
+            phase_distortion: EnumParam::new("Phase distortion", PhaseDistortion::Off),
+            pd_amount: FloatParam::new(
+                "PD amount",
+                0.5,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
                 },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            pd_attack: FloatParam::new(
+                "PD attack",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
             ),
-            lfo: FloatParam::new(
-                "LFO",
+            pd_decay: FloatParam::new(
+                "PD decay",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            pd_sustain: FloatParam::new(
+                "PD sustain",
+                1.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            pd_release: FloatParam::new(
+                "PD release",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            morph: FloatParam::new(
+                "Morph",
                 0.0,
                 FloatRange::Linear {
                     min: 0.0,
-                    max: 50.0,
+                    max: 1.0,
                 },
-            ).with_unit(" Hz"),
-            lfo_amp: FloatParam::new(
-                "LFO amp",
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // Added to the morph of each voice in proportion to its velocity.
+            velocity_morph: FloatParam::new(
+                "Velocity morph",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_step_size(0.01),
+            // How much the note's velocity scales its level. The exponential curve spans 40 dB.
+            velocity_curve: EnumParam::new("Velocity curve", VelocityCurve::Linear),
+            velocity_depth: FloatParam::new(
+                "Velocity depth",
                 1.0,
                 FloatRange::Linear {
                     min: 0.0,
                     max: 1.0,
                 },
-            ),
-            lfo_dest: EnumParam::new("LFO dest", LfoDest::None),
-        }
-    }
-}
-
-impl Seriessynth {
-    fn series(&self) -> [f32; HARMONICS_COUNT] {
-        let current_params_ref = Arc::clone(&self.params);
-        let mut series = [0.0; HARMONICS_COUNT];
-        for (i, harmonic) in current_params_ref.harmonics.iter().enumerate() {
-            series[i] = harmonic.nope.smoothed.next();
-        }
-        series
-    }
-}
-
-impl Seriessynth {
-    fn calculate(&mut self) -> f32 {
-        let series = self.series();
-        let params = Arc::clone(&self.params);
-        let higher_waveform = params.higher_waveform.value();
-        let amp_width = params.amp_width.value();
-        let noise = params.noise.smoothed.next();
-        let base_freq_factor = params.base_freq_factor.smoothed.next();
-        let base_freq_inverse_factor = params.base_freq_inverse_factor.smoothed.next();
-        let plus_n_cent = params.plus_n_cent.smoothed.next();
-        let lfo_hz = params.lfo.smoothed.next();
-        let lfo_amp = params.lfo_amp.smoothed.next();
-        let lfo_dest = params.lfo_dest.value();
-
-        let lfo_phase_delta = lfo_hz / self.sample_rate;
-        self.lfo_phase = (self.lfo_phase + lfo_phase_delta) % 1.0;
-        let lfo_phase_mod = if lfo_dest == LfoDest::Phase {
-            1.0 + lfo_amp * (self.lfo_phase * consts::TAU).sin()
-        } else {
-            1.0
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // Softer notes get a downward tilt of up to n^-2 on partial n.
+            velocity_brightness: FloatParam::new(
+                "Velocity brightness",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // How much poly pressure scales a held note's level, on top of its velocity.
+            pressure_depth: FloatParam::new(
+                "Pressure depth",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            unison: IntParam::new(
+                "Unison",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_UNISON as i32,
+                },
+            ),
+            // Cents between the outermost unison copies and the note.
+            detune: FloatParam::new(
+                "Detune",
+                10.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 100.0,
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" cent"),
+            spread: FloatParam::new(
+                "Spread",
+                0.5,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // How much of a cycle each unison copy's starting phase is randomised over.
+            unison_phase: FloatParam::new(
+                "Unison phase",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // Pans notes above middle C right and those below left, by up to this much two
+            // octaves away.
+            key_pan: FloatParam::new(
+                "Key pan",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // Mid/side width of the summed output, 0 being mono.
+            width: FloatParam::new(
+                "Width",
+                1.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 2.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            active_harmonics: IntParam::new(
+                "倍音数",
+                DEFAULT_ACTIVE_HARMONICS as i32,
+                IntRange::Linear {
+                    min: 1,
+                    max: HARMONICS_COUNT as i32,
+                },
+            ),
+            inharmonicity: FloatParam::new(
+                "Inharmonicity",
+                0.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 0.1,
+                    factor: FloatRange::skew_factor(-2.5),
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(5)),
+            stretch: FloatParam::new(
+                "Stretch",
+                1.0,
+                FloatRange::Linear {
+                    min: 0.5,
+                    max: 2.0,
+                },
+            )
+            .with_value_to_string(formatters::v2s_f32_rounded(3)),
+            // Harmonic n is scaled by 1/n^tilt. Negative tilts boost the upper harmonics.
+            tilt: FloatParam::new(
+                "倍音係数",
+                0.0,
+                FloatRange::Linear {
+                    min: -2.0,
+                    max: 3.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_step_size(0.01)
+            .with_value_to_string(Arc::new(|value| format!("1/N^{:.2}", value)))
+            .with_string_to_value(Arc::new(|string| {
+                let string = string.trim();
+                string.strip_prefix("1/N^").unwrap_or(string).parse().ok()
+            })),
+            // -1 leaves only the odd harmonics, 1 only the even ones.
+            odd_even: FloatParam::new(
+                "Odd/Even",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_step_size(0.01)
+            .with_value_to_string(Arc::new(|value| {
+                if value <= -1.0 {
+                    String::from("Odd only")
+                } else if value >= 1.0 {
+                    String::from("Even only")
+                } else {
+                    format!("{:.2}", value)
+                }
+            })),
+            spectral_filter: EnumParam::new("Spectral filter", SpectralFilterMode::Off),
+            spectral_cutoff: FloatParam::new(
+                "Spectral cutoff",
+                2_000.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(10.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+            spectral_resonance: FloatParam::new(
+                "Spectral resonance",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            ),
+            // How far the cutoff follows the note, relative to A4.
+            spectral_keytrack: FloatParam::new(
+                "Spectral keytrack",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // Octaves the cutoff moves by at the envelope's peak.
+            spectral_env_amount: FloatParam::new(
+                "Spectral env amount",
+                0.0,
+                FloatRange::Linear {
+                    min: -6.0,
+                    max: 6.0,
+                },
+            )
+            .with_step_size(0.01)
+            .with_unit(" oct"),
+            // 0 is a, 1 e, 2 i, 3 o and 4 u, with the formants crossfading in between.
+            vowel: FloatParam::new(
+                "Vowel",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 4.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(10.0))
+            .with_value_to_string(Arc::new(|value| {
+                let vowels = ["a", "e", "i", "o", "u"];
+                let index = value.round() as usize;
+                if (value - index as f32).abs() < 0.05 { String::from(vowels[index]) } else { format!("{:.2}", value) }
+            })),
+            formant_amount: FloatParam::new(
+                "Formant",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            filter_mode: EnumParam::new("Filter", FilterMode::Off),
+            filter_slope: EnumParam::new("Filter slope", FilterSlope::Db12),
+            filter_cutoff: FloatParam::new(
+                "Filter cutoff",
+                2_000.0,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(10.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
+            filter_resonance: FloatParam::new(
+                "Filter resonance",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            ),
+            filter_keytrack: FloatParam::new(
+                "Filter keytrack",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            // Octaves the cutoff moves by at the filter envelope's peak, and at full velocity.
+            filter_env_amount: FloatParam::new(
+                "Filter env amount",
+                0.0,
+                FloatRange::Linear {
+                    min: -6.0,
+                    max: 6.0,
+                },
+            )
+            .with_step_size(0.01)
+            .with_unit(" oct"),
+            filter_velocity: FloatParam::new(
+                "Filter velocity",
+                0.0,
+                FloatRange::Linear {
+                    min: -6.0,
+                    max: 6.0,
+                },
+            )
+            .with_step_size(0.01)
+            .with_unit(" oct"),
+            filter_attack: FloatParam::new(
+                "Filter attack",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            filter_decay: FloatParam::new(
+                "Filter decay",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            filter_sustain: FloatParam::new(
+                "Filter sustain",
+                1.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            filter_release: FloatParam::new(
+                "Filter release",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            osc_mode: EnumParam::new("Oscillator", OscMode::Fast),
+            nyquist_rolloff: FloatParam::new(
+                "Nyquist roll-off",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 0.5,
+                },
+            )
+            .with_unit("%")
+            .with_value_to_string(formatters::v2s_f32_percentage(0))
+            .with_string_to_value(formatters::s2v_f32_percentage()),
+            noise: FloatParam::new(
+                "Noise",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            ),
+            noise_color: EnumParam::new("Noise color", NoiseColor::White),
+            noise_attack: FloatParam::new(
+                "Noise attack",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            noise_decay: FloatParam::new(
+                "Noise decay",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            noise_sustain: FloatParam::new(
+                "Noise sustain",
+                1.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            seed: IntParam::new(
+                "Seed",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: 65535,
+                },
+            ),
+            // Restarts the random sequence from `seed` on every reset, so renders come out bit-identical.
+            deterministic: BoolParam::new("Deterministic", false),
+            base_freq_factor: IntParam::new(
+                "Base Freq factor",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: 23,
+                },
+            ),
+            base_freq_inverse_factor: IntParam::new(
+                "Base Freq inverse factor",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: 23,
+                },
+            ),
+            plus_n_cent: IntParam::new(
+                "+ N Cent",
+                0,
+                IntRange::Linear {
+                    min: -100,
+                    max: 100,
+                },
+            ),
+            lfo: FloatParam::new(
+                "LFO",
+                0.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 50.0,
+                },
+            ).with_unit(" Hz"),
+            lfo_amp: FloatParam::new(
+                "LFO amp",
+                1.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 1.0,
+                },
+            ),
+            lfo_dest: EnumParam::new("LFO dest", LfoDest::None),
+        }
+    }
+}
+
+// Left and right gains for a pan between -1 and 1, unity for both in the centre.
+fn pan_gains(pan: f32) -> (f32, f32) {
+    let pan = pan.clamp(-1.0, 1.0);
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+
+fn tilt_coefficient(index: usize, tilt: f32) -> f32 {
+    (index as f32 + 1.0).powf(-tilt)
+}
+
+// Gain of harmonic `k` for an odd/even balance between -1 and 1.
+fn odd_even_gain(k: usize, balance: f32) -> f32 {
+    if k % 2 == 1 {
+        (1.0 - balance).min(1.0)
+    } else {
+        (1.0 + balance).min(1.0)
+    }
+}
+
+// Per-partial levels of a waveform tail.
+enum TailLevels<'a> {
+    Flat,
+    // Levels that change little from one partial to the next of the same parity.
+    Smooth(&'a dyn Fn(usize) -> f32),
+    Exact(&'a dyn Fn(usize) -> f32),
+}
+
+impl Waveform {
+    // Fourier coefficient of partial `k`, relative to a fundamental of 1.
+    fn amplitude(&self, k: usize) -> f32 {
+        match self {
+            Waveform::None => 0.0,
+            Waveform::Sawtooth => 1.0 / k as f32,
+            Waveform::Square if k % 2 == 1 => 1.0 / k as f32,
+            Waveform::Triangle if k % 2 == 1 => {
+                let sign = if k % 4 == 1 { 1.0 } else { -1.0 };
+                sign / (k * k) as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    // The part of the waveform from partial `first` up to Nyquist. Long tails come from a PolyBLEP
+    // oscillator with the share of the partials below `first` subtracted back out. Every Fast mode
+    // tail droops towards Nyquist as `blep_response` says. Per-partial `level`s are exact in Exact
+    // mode. In Fast mode smooth levels are held flat above the lowest `BLEP_MIN_PARTIALS` tail
+    // partials, separately for the odd and the even ones.
+    fn tail(&self, mode: &OscMode, first: usize, phase: f32, phase_delta: f32, limit: &BandLimit, levels: TailLevels) -> f32 {
+        let (level, smooth) = match levels {
+            TailLevels::Flat => (None, true),
+            TailLevels::Smooth(level) => (Some(level), true),
+            TailLevels::Exact(level) => (Some(level), false),
         };
-        let lfo_gain_mod = if lfo_dest == LfoDest::Gain {
-            1.0 + lfo_amp * (self.lfo_phase * consts::TAU).sin()
+        let drooped = |k: usize| self.amplitude(k) * oscillator::blep_response(k as f32 * phase_delta);
+        // The roll-off is taken off the BLEP one partial at a time, so Fast mode keeps it to as
+        // many partials as the additive tail would render.
+        let narrowed = limit.with_rolloff_partials(oscillator::BLEP_MIN_PARTIALS);
+        let limit = if *mode == OscMode::Fast { &narrowed } else { limit };
+        if *self == Waveform::None {
+            0.0
+        } else if *mode == OscMode::Fast && smooth && limit.end() >= first + oscillator::BLEP_MIN_PARTIALS {
+            // A shaped tail is shaped partial by partial for its lowest partials only, and the rest
+            // comes out of the BLEP at the level of the first odd and the first even partial above
+            // them. That keeps the cost the same however low the note.
+            let (split, shaped, odd, even) = match level {
+                Some(level) => {
+                    let split = first + oscillator::BLEP_MIN_PARTIALS;
+                    let shaped = oscillator::sine_series(mode, first..split, phase, |k| drooped(k) * limit.gain(k) * level(k));
+                    (split, shaped, level(split | 1), level(split + split % 2))
+                }
+                None => (first, 0.0, 1.0, 1.0),
+            };
+            let held = |k: usize| if k % 2 == 1 { odd } else { even };
+            // The even partials of a saw are a saw at twice the frequency and half the level. The
+            // square and the triangle have none.
+            let classic = match self {
+                Waveform::Sawtooth => {
+                    let octave = 0.5 * oscillator::saw((2.0 * phase).fract(), 2.0 * phase_delta);
+                    odd * (oscillator::saw(phase, phase_delta) - octave) + even * octave
+                }
+                Waveform::Square => odd * oscillator::square(phase, phase_delta),
+                _ => odd * oscillator::triangle(phase, phase_delta),
+            };
+            // The classic waveform has every partial at full level, so the roll-off is taken off
+            // as it comes out of the BLEP.
+            let faded = oscillator::sine_series(mode, limit.rolloff_start().max(split)..limit.end(), phase, |k| {
+                drooped(k) * (1.0 - limit.gain(k)) * held(k)
+            });
+            shaped + classic - oscillator::sine_series(mode, 1..split, phase, |k| drooped(k) * held(k)) - faded
         } else {
-            1.0
-        };
+            // Fast mode droops the additive tail too, so that the top octave does not jump when a
+            // note crosses over to the BLEP.
+            let amplitude = |k: usize| if *mode == OscMode::Fast { drooped(k) } else { self.amplitude(k) };
+            oscillator::sine_series(mode, first..limit.end(), phase, |k| {
+                amplitude(k) * limit.gain(k) * level.map_or(1.0, |level| level(k))
+            })
+        }
+    }
+}
 
-        let cent_factor = 2f32.powf(plus_n_cent as f32 / 1200.0);
-        let freq_factor = (base_freq_factor as f32) / (base_freq_inverse_factor as f32);
+// Per-sample envelope increments, infinite when the stage is instant.
+#[derive(Clone, Copy)]
+struct Envelope {
+    attack: f32,
+    hold: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+    sample_period: f32,
+}
 
+impl Envelope {
+    fn step(time: f32, sample_rate: f32) -> f32 {
+        if time < EPSILON {
+            f32::INFINITY
+        } else {
+            1.0 / (sample_rate * time)
+        }
+    }
+}
 
-        let mut final_wave = 0.0;
-        for voice_queue in self.voices.values_mut() {
-            if voice_queue.len() == 0 {
-                continue;
-            }
-            let mut kill = false;
-            for voice in voice_queue.iter_mut() {
-                let phase_delta = (voice.midi_note_freq * cent_factor * freq_factor * lfo_phase_mod) / self.sample_rate;
-                let mut wave = 0.0;
-                for i in 0..HARMONICS_COUNT {
-                    wave +=  match amp_width {
-                            AmpWidth::One => 1.0,
-                            AmpWidth::N => 1.0 / (i as f32 + 1.0),
-                            AmpWidth::N2 => 1.0 / (((i as f32 + 1.0) * (i as f32 + 1.0)))
-                        } * series[i] * (((i+1) as f32) * voice.phase * consts::TAU).sin();
+impl DecayClock {
+    // Level of a tail partial whose decay and release run `speed` times faster than those of
+    // `master`, as its own envelope would have stepped it.
+    fn level(&self, master: &EnvelopeState, speed: f32, envelope: &Envelope) -> f32 {
+        let decayed = (1.0 - envelope.decay * speed * self.decay_samples).max(envelope.sustain);
+        match master.ahdsr {
+            AHDSR::A | AHDSR::H => master.level,
+            // On the sample the master enters the decay nothing has been counted yet, and the
+            // partial is still at the top.
+            AHDSR::D | AHDSR::S => decayed,
+            AHDSR::R | AHDSR::DEAD => {
+                // A note released before its decay began releases from where the attack left it.
+                let released = if self.decay_samples > 0.0 { decayed } else { self.release_level };
+                (released - envelope.release * speed * self.release_samples).max(0.0)
+            }
+        }
+    }
+}
+
+impl EnvelopeState {
+    fn new() -> Self {
+        Self {
+            ahdsr: AHDSR::A,
+            level: 0.0,
+            hold: 0.0,
+        }
+    }
+
+    // Steps one sample and returns false once the release has run out.
+    fn next(&mut self, envelope: &Envelope) -> bool {
+        match self.ahdsr {
+            AHDSR::A => {
+                self.level += envelope.attack;
+                if self.level >= 1.0 {
+                    self.level = 1.0;
+                    self.ahdsr = AHDSR::H;
                 }
-                let nyquist_index = ((self.sample_rate as f32) / (voice.midi_note_freq * freq_factor * cent_factor as f32)).floor() as usize;
-                if higher_waveform == Waveform::Square {
-                    for i in (HARMONICS_COUNT >> 1)..(nyquist_index >> 1) {
-                        wave += (1.0 / (2.0 * i as f32) as f32)
-                            * ((i as f32) * voice.phase * consts::TAU).sin();
-                    }
+            }
+            AHDSR::H => {
+                self.hold += envelope.sample_period;
+                if self.hold + envelope.sample_period >= envelope.hold {
+                    self.ahdsr = AHDSR::D;
                 }
-                if higher_waveform == Waveform::Triangle {
-                    for i in (HARMONICS_COUNT>>1)..(nyquist_index >> 1) {
-                        wave += if i % 2 == 0 {-1.0} else {1.0} * (1.0 / (2.0 * i as f32)) * (1.0 / (2.0 * i as f32))
-                            * ((i as f32) * voice.phase * consts::TAU).sin();
-                    }
+            }
+            AHDSR::D => {
+                self.level = (self.level - envelope.decay).max(envelope.sustain);
+                if self.level <= envelope.sustain {
+                    self.ahdsr = AHDSR::S;
                 }
-                if higher_waveform == Waveform::Sawtooth {
-                    for i in HARMONICS_COUNT+1..nyquist_index {
-                        wave += (1.0 / i as f32)
-                            * ((i as f32) * voice.phase * consts::TAU).sin();
-                    }
+            }
+            AHDSR::S => {
+
+            }
+            AHDSR::R | AHDSR::DEAD => {
+                self.level -= envelope.release;
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+// Everything the voices need to render one block, read from the params once up front.
+struct Block {
+    // Amplitudes of snapshots A and B, which every voice morphs between by its own amount.
+    coefficients: [[f32; HARMONICS_COUNT]; MAX_BLOCK_SIZE],
+    coefficients_b: [[f32; HARMONICS_COUNT]; MAX_BLOCK_SIZE],
+    morph: [f32; MAX_BLOCK_SIZE],
+    velocity_morph: f32,
+    velocity_curve: VelocityCurve,
+    velocity_depth: f32,
+    velocity_brightness: f32,
+    pressure_depth: f32,
+    layer_coefficients: [[f32; HARMONICS_COUNT]; MAX_BLOCK_SIZE],
+    // The layer's frequency relative to the note.
+    layer_freq_factor: f32,
+    layer_mix: [f32; MAX_BLOCK_SIZE],
+    sub_level: [f32; MAX_BLOCK_SIZE],
+    sub_factor: f32,
+    fm_source: FmSource,
+    fm_ratio: f32,
+    fm_index: [f32; MAX_BLOCK_SIZE],
+    fm_feedback: f32,
+    fm_envelope: Envelope,
+    ring_mode: RingMode,
+    // The ring modulator's frequency relative to the note, or in Hz when not tracking.
+    ring_tracking: RingTracking,
+    ring_freq_factor: f32,
+    ring_freq: [f32; MAX_BLOCK_SIZE],
+    ring_mix: [f32; MAX_BLOCK_SIZE],
+    phase_distortion: PhaseDistortion,
+    pd_amount: [f32; MAX_BLOCK_SIZE],
+    pd_envelope: Envelope,
+    odd_even: [f32; MAX_BLOCK_SIZE],
+    // (cos, sin) of each harmonic's phase offset, held for the whole block.
+    phase_offsets: [(f32, f32); HARMONICS_COUNT],
+    // Frequency of each partial relative to the fundamental.
+    ratios: [f32; HARMONICS_COUNT],
+    // Bandwidth enhancement after Loris: each partial's amplitude is scaled by
+    // `carrier + noise * ζ`, where ζ is the voice's lowpassed noise for that partial.
+    bandwidth_carrier: [f32; HARMONICS_COUNT],
+    bandwidth_noise: [f32; HARMONICS_COUNT],
+    bandwidth_lowpass: f32,
+    noisy: bool,
+    harmonic: bool,
+    active_harmonics: usize,
+    unison: usize,
+    // Frequency factor and pan of each unison copy.
+    unison_detune: [f32; MAX_UNISON],
+    unison_pan: [f32; MAX_UNISON],
+    unison_gain: f32,
+    // Pan of each partial on top of its copy's, only used while `panned`.
+    pans: [f32; HARMONICS_COUNT],
+    panned: bool,
+    key_pan: f32,
+    width: [f32; MAX_BLOCK_SIZE],
+    // Set from the output layout. A mono output gets the plain sum, with every pan ignored.
+    mono: bool,
+    phase_mod: [f32; MAX_BLOCK_SIZE],
+    gain_mod: [f32; MAX_BLOCK_SIZE],
+    higher_waveform: Waveform,
+    spectral_filter: SpectralFilterMode,
+    spectral_cutoff: [f32; MAX_BLOCK_SIZE],
+    spectral_resonance: f32,
+    spectral_keytrack: f32,
+    spectral_env_amount: f32,
+    vowel: [f32; MAX_BLOCK_SIZE],
+    formant_amount: f32,
+    filter_mode: FilterMode,
+    filter_slope: FilterSlope,
+    filter_cutoff: [f32; MAX_BLOCK_SIZE],
+    filter_resonance: f32,
+    filter_keytrack: f32,
+    filter_env_amount: f32,
+    filter_velocity: f32,
+    filter_envelope: Envelope,
+    osc_mode: OscMode,
+    nyquist_rolloff: f32,
+    noise: f32,
+    noise_color: NoiseColor,
+    noise_envelope: Envelope,
+    freq_factor: f32,
+    decay_tilt: f32,
+    envelope: Envelope,
+    partial_envelopes: [Envelope; HARMONICS_COUNT],
+    // True while every partial follows `envelope`.
+    uniform_envelope: bool,
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Self {
+            coefficients: [[0.0; HARMONICS_COUNT]; MAX_BLOCK_SIZE],
+            coefficients_b: [[0.0; HARMONICS_COUNT]; MAX_BLOCK_SIZE],
+            morph: [0.0; MAX_BLOCK_SIZE],
+            velocity_morph: 0.0,
+            velocity_curve: VelocityCurve::Linear,
+            velocity_depth: 1.0,
+            velocity_brightness: 0.0,
+            pressure_depth: 0.0,
+            layer_coefficients: [[0.0; HARMONICS_COUNT]; MAX_BLOCK_SIZE],
+            layer_freq_factor: 1.0,
+            layer_mix: [0.0; MAX_BLOCK_SIZE],
+            sub_level: [0.0; MAX_BLOCK_SIZE],
+            sub_factor: 0.5,
+            fm_source: FmSource::Off,
+            fm_ratio: 1.0,
+            fm_index: [0.0; MAX_BLOCK_SIZE],
+            fm_feedback: 0.0,
+            fm_envelope: Envelope {
+                attack: f32::INFINITY,
+                hold: 0.0,
+                decay: f32::INFINITY,
+                sustain: 1.0,
+                release: f32::INFINITY,
+                sample_period: 0.0,
+            },
+            ring_mode: RingMode::Off,
+            ring_tracking: RingTracking::Note,
+            ring_freq_factor: 1.0,
+            ring_freq: [100.0; MAX_BLOCK_SIZE],
+            ring_mix: [1.0; MAX_BLOCK_SIZE],
+            phase_distortion: PhaseDistortion::Off,
+            pd_amount: [0.5; MAX_BLOCK_SIZE],
+            pd_envelope: Envelope {
+                attack: f32::INFINITY,
+                hold: 0.0,
+                decay: f32::INFINITY,
+                sustain: 1.0,
+                release: f32::INFINITY,
+                sample_period: 0.0,
+            },
+            odd_even: [0.0; MAX_BLOCK_SIZE],
+            phase_offsets: [(1.0, 0.0); HARMONICS_COUNT],
+            ratios: array::from_fn(|i| (i + 1) as f32),
+            bandwidth_carrier: [1.0; HARMONICS_COUNT],
+            bandwidth_noise: [0.0; HARMONICS_COUNT],
+            bandwidth_lowpass: 0.0,
+            noisy: false,
+            harmonic: true,
+            active_harmonics: DEFAULT_ACTIVE_HARMONICS,
+            unison: 1,
+            unison_detune: [1.0; MAX_UNISON],
+            unison_pan: [0.0; MAX_UNISON],
+            unison_gain: 1.0,
+            pans: [0.0; HARMONICS_COUNT],
+            panned: false,
+            key_pan: 0.0,
+            width: [1.0; MAX_BLOCK_SIZE],
+            mono: false,
+            phase_mod: [1.0; MAX_BLOCK_SIZE],
+            gain_mod: [1.0; MAX_BLOCK_SIZE],
+            higher_waveform: Waveform::None,
+            spectral_filter: SpectralFilterMode::Off,
+            spectral_cutoff: [2_000.0; MAX_BLOCK_SIZE],
+            spectral_resonance: 0.0,
+            spectral_keytrack: 0.0,
+            spectral_env_amount: 0.0,
+            vowel: [0.0; MAX_BLOCK_SIZE],
+            formant_amount: 0.0,
+            filter_mode: FilterMode::Off,
+            filter_slope: FilterSlope::Db12,
+            filter_cutoff: [2_000.0; MAX_BLOCK_SIZE],
+            filter_resonance: 0.0,
+            filter_keytrack: 0.0,
+            filter_env_amount: 0.0,
+            filter_velocity: 0.0,
+            filter_envelope: Envelope {
+                attack: f32::INFINITY,
+                hold: 0.0,
+                decay: f32::INFINITY,
+                sustain: 1.0,
+                release: f32::INFINITY,
+                sample_period: 0.0,
+            },
+            osc_mode: OscMode::Fast,
+            nyquist_rolloff: 0.0,
+            noise: 0.0,
+            noise_color: NoiseColor::White,
+            noise_envelope: Envelope {
+                attack: f32::INFINITY,
+                hold: 0.0,
+                decay: f32::INFINITY,
+                sustain: 1.0,
+                release: f32::INFINITY,
+                sample_period: 0.0,
+            },
+            freq_factor: 1.0,
+            decay_tilt: 1.0,
+            envelope: Envelope {
+                attack: f32::INFINITY,
+                hold: 0.0,
+                decay: f32::INFINITY,
+                sustain: 1.0,
+                release: f32::INFINITY,
+                sample_period: 0.0,
+            },
+            partial_envelopes: [Envelope {
+                attack: f32::INFINITY,
+                hold: 0.0,
+                decay: f32::INFINITY,
+                sustain: 1.0,
+                release: f32::INFINITY,
+                sample_period: 0.0,
+            }; HARMONICS_COUNT],
+            uniform_envelope: true,
+        }
+    }
+}
+
+impl Block {
+    fn update(&mut self, params: &SeriessynthParams, lfo_phase: &mut f32, sample_rate: f32, block_len: usize) {
+        let lfo_phase_delta = params.lfo.value() / sample_rate;
+        let lfo_amp = params.lfo_amp.value();
+        let lfo_dest = params.lfo_dest.value();
+        params.morph.smoothed.next_block(&mut self.morph, block_len);
+        params.odd_even.smoothed.next_block(&mut self.odd_even, block_len);
+        params.vowel.smoothed.next_block(&mut self.vowel, block_len);
+        self.velocity_morph = params.velocity_morph.value();
+        self.velocity_curve = params.velocity_curve.value();
+        self.velocity_depth = params.velocity_depth.value();
+        self.velocity_brightness = params.velocity_brightness.value();
+        self.pressure_depth = params.pressure_depth.value();
+        for n in 0..block_len {
+            *lfo_phase = (*lfo_phase + lfo_phase_delta) % 1.0;
+            let lfo = match lfo_dest {
+                LfoDest::None => 0.0,
+                _ => lfo_amp * (*lfo_phase * consts::TAU).sin(),
+            };
+            self.phase_mod[n] = if lfo_dest == LfoDest::Phase { 1.0 + lfo } else { 1.0 };
+            self.gain_mod[n] = if lfo_dest == LfoDest::Gain { 1.0 + lfo } else { 1.0 };
+            match lfo_dest {
+                LfoDest::Morph => self.morph[n] += lfo,
+                LfoDest::Balance => self.odd_even[n] = (self.odd_even[n] + lfo).clamp(-1.0, 1.0),
+                // A full depth LFO sweeps two vowels either way.
+                LfoDest::Vowel => self.vowel[n] += 2.0 * lfo,
+                _ => (),
+            }
+        }
+
+        let mut tilt = [0.0; MAX_BLOCK_SIZE];
+        params.tilt.smoothed.next_block(&mut tilt, block_len);
+        let inharmonicity = params.inharmonicity.value();
+        let stretch = params.stretch.value();
+        self.active_harmonics = params.active_harmonics.value() as usize;
+        self.harmonic = true;
+        // The noise is lowpassed at 500 Hz like Loris does and scaled to a variance of 1/2, so
+        // that the partial keeps its energy whatever its bandwidth.
+        self.bandwidth_lowpass = 1.0 - (-consts::TAU * 500.0 / sample_rate).exp();
+        let noise_gain = (1.5 * (2.0 - self.bandwidth_lowpass) / self.bandwidth_lowpass).sqrt();
+        self.noisy = false;
+        self.panned = false;
+        let mut series = [0.0; MAX_BLOCK_SIZE];
+        for (i, harmonic) in params.harmonics.iter().enumerate().take(self.active_harmonics) {
+            let mut coefficient = [0.0; MAX_BLOCK_SIZE];
+            for ((coefficient, tilt), odd_even) in coefficient.iter_mut().zip(&tilt[..block_len]).zip(&self.odd_even) {
+                *coefficient = tilt_coefficient(i, *tilt) * odd_even_gain(i + 1, *odd_even);
+            }
+            harmonic.nope.smoothed.next_block(&mut series, block_len);
+            for ((coefficients, value), coefficient) in self.coefficients.iter_mut().zip(&series[..block_len]).zip(coefficient) {
+                coefficients[i] = coefficient * value;
+            }
+            harmonic.nope_b.smoothed.next_block(&mut series, block_len);
+            for ((coefficients, value), coefficient) in self.coefficients_b.iter_mut().zip(&series[..block_len]).zip(coefficient) {
+                coefficients[i] = coefficient * value;
+            }
+            harmonic.phase.smoothed.next_block(&mut series, block_len);
+            let (sin, cos) = series[block_len - 1].to_radians().sin_cos();
+            self.phase_offsets[i] = (cos, sin);
+
+            // Stiff string partials sit at n * sqrt(1 + B * n^2), optionally stretched by n^stretch.
+            let n = (i + 1) as f32;
+            let ratio = harmonic.ratio.value();
+            self.ratios[i] = if ratio > 0.0 { ratio } else { n.powf(stretch) * (1.0 + inharmonicity * n * n).sqrt() };
+            self.harmonic &= self.ratios[i] == n;
+
+            let bandwidth = harmonic.bandwidth.value();
+            self.bandwidth_carrier[i] = (1.0 - bandwidth).sqrt();
+            self.bandwidth_noise[i] = (2.0 * bandwidth).sqrt() * noise_gain;
+            self.noisy |= bandwidth > 0.0;
+
+            self.pans[i] = if self.mono { 0.0 } else { harmonic.pan.value() };
+            self.panned |= self.pans[i] != 0.0;
+        }
+
+        for (i, layer) in params.layer.iter().enumerate().take(self.active_harmonics) {
+            layer.amplitude.smoothed.next_block(&mut series, block_len);
+            for (coefficients, value) in self.layer_coefficients.iter_mut().zip(&series[..block_len]) {
+                coefficients[i] = *value;
+            }
+        }
+
+        let cent_factor = 2f32.powf(params.plus_n_cent.value() as f32 / 1200.0);
+        let freq_factor = (params.base_freq_factor.value() as f32) / (params.base_freq_inverse_factor.value() as f32);
+        self.freq_factor = freq_factor * cent_factor;
+        let layer_cent_factor = 2f32.powf(params.layer_cent.value() as f32 / 1200.0);
+        let layer_freq_factor = (params.layer_freq_factor.value() as f32) / (params.layer_freq_inverse_factor.value() as f32);
+        self.layer_freq_factor = layer_freq_factor * layer_cent_factor;
+        params.layer_mix.smoothed.next_block(&mut self.layer_mix, block_len);
+        params.sub_level.smoothed.next_block(&mut self.sub_level, block_len);
+        self.sub_factor = 0.5f32.powi(params.sub_octave.value());
+        self.fm_source = params.fm_source.value();
+        self.fm_ratio = params.fm_ratio.value();
+        params.fm_index.smoothed.next_block(&mut self.fm_index, block_len);
+        self.fm_feedback = params.fm_feedback.value();
+        self.fm_envelope = Envelope {
+            attack: Envelope::step(params.fm_attack.value(), sample_rate),
+            hold: 0.0,
+            decay: Envelope::step(params.fm_decay.value(), sample_rate),
+            sustain: params.fm_sustain.value(),
+            release: Envelope::step(params.fm_release.value(), sample_rate),
+            sample_period: 1.0 / sample_rate,
+        };
+        self.ring_mode = params.ring_mode.value();
+        self.ring_tracking = params.ring_tracking.value();
+        self.ring_freq_factor = (params.ring_freq_factor.value() as f32) / (params.ring_freq_inverse_factor.value() as f32);
+        params.ring_freq.smoothed.next_block(&mut self.ring_freq, block_len);
+        params.ring_mix.smoothed.next_block(&mut self.ring_mix, block_len);
+        self.phase_distortion = params.phase_distortion.value();
+        params.pd_amount.smoothed.next_block(&mut self.pd_amount, block_len);
+        self.pd_envelope = Envelope {
+            attack: Envelope::step(params.pd_attack.value(), sample_rate),
+            hold: 0.0,
+            decay: Envelope::step(params.pd_decay.value(), sample_rate),
+            sustain: params.pd_sustain.value(),
+            release: Envelope::step(params.pd_release.value(), sample_rate),
+            sample_period: 1.0 / sample_rate,
+        };
+        self.higher_waveform = params.higher_waveform.value();
+        // The copies are spread evenly from -1 to 1 and summed at roughly constant loudness.
+        self.unison = params.unison.value() as usize;
+        let detune = params.detune.value();
+        let spread = if self.mono { 0.0 } else { params.spread.value() };
+        for (i, (unison_detune, unison_pan)) in self.unison_detune.iter_mut().zip(&mut self.unison_pan).enumerate() {
+            let position = if self.unison > 1 { 2.0 * i as f32 / (self.unison - 1) as f32 - 1.0 } else { 0.0 };
+            *unison_detune = 2f32.powf(detune * position / 1200.0);
+            *unison_pan = spread * position;
+        }
+        self.unison_gain = 1.0 / (self.unison as f32).sqrt();
+        self.key_pan = if self.mono { 0.0 } else { params.key_pan.value() };
+        params.width.smoothed.next_block(&mut self.width, block_len);
+        self.spectral_filter = params.spectral_filter.value();
+        params.spectral_cutoff.smoothed.next_block(&mut self.spectral_cutoff, block_len);
+        self.spectral_resonance = params.spectral_resonance.value();
+        self.spectral_keytrack = params.spectral_keytrack.value();
+        self.spectral_env_amount = params.spectral_env_amount.value();
+        self.formant_amount = params.formant_amount.value();
+        self.filter_mode = params.filter_mode.value();
+        self.filter_slope = params.filter_slope.value();
+        params.filter_cutoff.smoothed.next_block(&mut self.filter_cutoff, block_len);
+        self.filter_resonance = params.filter_resonance.value();
+        self.filter_keytrack = params.filter_keytrack.value();
+        self.filter_env_amount = params.filter_env_amount.value();
+        self.filter_velocity = params.filter_velocity.value();
+        self.filter_envelope = Envelope {
+            attack: Envelope::step(params.filter_attack.value(), sample_rate),
+            hold: 0.0,
+            decay: Envelope::step(params.filter_decay.value(), sample_rate),
+            sustain: params.filter_sustain.value(),
+            release: Envelope::step(params.filter_release.value(), sample_rate),
+            sample_period: 1.0 / sample_rate,
+        };
+        self.osc_mode = params.osc_mode.value();
+        self.nyquist_rolloff = params.nyquist_rolloff.value();
+        self.noise = params.noise.value();
+        self.noise_color = params.noise_color.value();
+        self.noise_envelope = Envelope {
+            attack: Envelope::step(params.noise_attack.value(), sample_rate),
+            hold: 0.0,
+            decay: Envelope::step(params.noise_decay.value(), sample_rate),
+            sustain: params.noise_sustain.value(),
+            release: 0.0,
+            sample_period: 1.0 / sample_rate,
+        };
+        self.envelope = Envelope {
+            attack: Envelope::step(params.attack.value(), sample_rate),
+            hold: params.hold.value(),
+            decay: Envelope::step(params.decay.value(), sample_rate),
+            sustain: params.sustain.value(),
+            release: Envelope::step(params.release.value(), sample_rate),
+            sample_period: 1.0 / sample_rate,
+        };
+
+        let attack_curve = params.env_attack_curve.value();
+        let decay_curve = params.env_decay_curve.value();
+        self.decay_tilt = params.decay_tilt.value();
+        self.uniform_envelope = true;
+        for (i, harmonic) in params.harmonics.iter().enumerate().take(self.active_harmonics) {
+            let n = (i + 1) as f32;
+            let attack_scale = harmonic.attack.value() * n.powf(attack_curve);
+            let decay_scale = harmonic.decay.value() * n.powf(decay_curve) / self.decay_tilt.powi(i as i32);
+            self.uniform_envelope &= attack_scale == 1.0 && decay_scale == 1.0;
+            self.partial_envelopes[i] = Envelope {
+                attack: Envelope::step(params.attack.value() * attack_scale, sample_rate),
+                decay: Envelope::step(params.decay.value() * decay_scale, sample_rate),
+                release: Envelope::step(params.release.value() * decay_scale, sample_rate),
+                ..self.envelope
+            };
+        }
+    }
+}
+
+impl Voice {
+    fn new(note: u8, velocity: f32, seed: u32, random_phase: f32) -> Self {
+        let mut rng = Rng::new(seed);
+        let voice = Voice {
+            oscillators: array::from_fn(|_| Oscillator {
+                phase: if random_phase > 0.0 { (rng.next_f32() + 1.0) * 0.5 * random_phase } else { 0.0 },
+                partial_phases: [0.0; HARMONICS_COUNT],
+                harmonic: true,
+            }),
+            layer_phase: 0.0,
+            sub_phase: 0.0,
+            fm_phase: 0.0,
+            fm_output: [0.0; 2],
+            fm_envelope: EnvelopeState::new(),
+            ring_phase: 0.0,
+            pd_envelope: EnvelopeState::new(),
+            midi_note_freq: util::midi_note_to_freq(note),
+            midi_note_gain: Smoother::new(SmoothingStyle::Linear(5.0)),
+            velocity,
+            key: ((note as f32 - 60.0) / 24.0).clamp(-1.0, 1.0),
+            envelope: EnvelopeState::new(),
+            filter: [[Svf::default(); 2]; 2],
+            filter_envelope: EnvelopeState::new(),
+            noise: Noise::new(rng.next_u32()),
+            partial_noise: [0.0; HARMONICS_COUNT],
+            noise_envelope: EnvelopeState::new(),
+            partial_envelopes: [EnvelopeState::new(); HARMONICS_COUNT],
+            uniform_envelope: true,
+            clock: DecayClock {
+                decay_samples: 0.0,
+                release_samples: 0.0,
+                release_level: 0.0,
+            },
+            dead: 0.0,
+        };
+        voice.midi_note_gain.reset(1.0);
+        voice
+    }
+
+    // Adds this voice to `left` and `right` and returns false once its envelope has run out.
+    fn render(&mut self, block: &Block, sample_rate: f32, left: &mut [f32], right: &mut [f32]) -> bool {
+        let freq = self.midi_note_freq * block.freq_factor;
+        let voice_pan = block.key_pan * self.key;
+        for oscillator in self.oscillators.iter_mut() {
+            if oscillator.harmonic && !block.harmonic {
+                // Pick the partials up where the shared phase left them.
+                for (k, partial_phase) in oscillator.partial_phases.iter_mut().enumerate() {
+                    *partial_phase = ((k + 1) as f32 * oscillator.phase).fract();
                 }
-                if noise > EPSILON {
-                    let f: f32 = rand::random_range(-noise..noise);
-                    wave += f;
+            }
+            oscillator.harmonic = block.harmonic;
+        }
+        if self.uniform_envelope && !block.uniform_envelope {
+            // Let the partials carry on from wherever the master envelope is.
+            self.partial_envelopes = [self.envelope; HARMONICS_COUNT];
+        }
+        self.uniform_envelope = block.uniform_envelope;
+
+        let active_harmonics = block.active_harmonics;
+        let (mut filter, mut formants) = (None, None);
+        let mut spectral_gains = [1.0; HARMONICS_COUNT];
+        let velocity_gain = match block.velocity_curve {
+            VelocityCurve::Linear => 1.0 - block.velocity_depth + block.velocity_depth * self.velocity,
+            VelocityCurve::Exponential => util::db_to_gain(40.0 * block.velocity_depth * (self.velocity - 1.0)),
+            VelocityCurve::Fixed => 1.0,
+        };
+        // Exponent of the downward tilt that velocity brightness puts on softer notes.
+        let darkening = 2.0 * block.velocity_brightness * (1.0 - self.velocity);
+        for (n, (left, right)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+            match self.envelope.ahdsr {
+                AHDSR::D | AHDSR::S => self.clock.decay_samples += 1.0,
+                AHDSR::R | AHDSR::DEAD => self.clock.release_samples += 1.0,
+                _ => (),
+            }
+            let mut alive = self.envelope.next(&block.envelope);
+            self.noise_envelope.next(&block.noise_envelope);
+            self.fm_envelope.next(&block.fm_envelope);
+            self.pd_envelope.next(&block.pd_envelope);
+            // The spectral filter follows its cutoff and the envelope, and the formants follow the
+            // vowel, a few samples at a time.
+            let update_spectrum = n % SPECTRAL_INTERVAL == 0;
+            if update_spectrum {
+                filter = (block.spectral_filter != SpectralFilterMode::Off).then(|| {
+                    let cutoff = block.spectral_cutoff[n]
+                        * (freq / 440.0).powf(block.spectral_keytrack)
+                        * 2f32.powf(block.spectral_env_amount * self.envelope.level);
+                    SpectralFilter::new(block.spectral_filter, cutoff, block.spectral_resonance)
+                });
+                formants = (block.formant_amount > 0.0).then(|| Formants::new(block.vowel[n], block.formant_amount));
+            }
+            let spectral_gain = |freq: f32| {
+                filter.as_ref().map_or(1.0, |filter| filter.gain(freq))
+                    * formants.as_ref().map_or(1.0, |formants| formants.gain(freq))
+            };
+            if update_spectrum && (filter.is_some() || formants.is_some() || darkening > 0.0) {
+                for (gain, ratio) in spectral_gains[..active_harmonics].iter_mut().zip(&block.ratios) {
+                    *gain = spectral_gain(ratio * freq) * ratio.max(1.0).powf(-darkening);
                 }
-                voice.phase += phase_delta;
-                if voice.phase >= 1.0 {
-                    voice.phase -= 1.0;
+            }
+            if !block.uniform_envelope {
+                for (state, envelope) in self.partial_envelopes[..active_harmonics].iter_mut().zip(&block.partial_envelopes) {
+                    alive |= state.next(envelope);
                 }
-                match voice.ahdsr {
-                    AHDSR::A => {
-                        if self.params.attack.smoothed.next() < EPSILON {
-                            voice.ahdsr = AHDSR::H;
-                            voice.envelope = 1.0;
-                        } else {
-                            voice.envelope += 1.0 / (self.sample_rate * self.params.attack.smoothed.next());
-                            if voice.envelope >= 1.0 {
-                                voice.envelope = 1.0;
-                                voice.ahdsr = AHDSR::H;
-                            }
-                        }
-                    }
-                    AHDSR::H => {
-                        voice.hold += 1.0 / self.sample_rate;
-                        if voice.hold + 1.0 / self.sample_rate >= self.params.hold.smoothed.next() {
-                            voice.ahdsr = AHDSR::D;
-                        }
-                    }
-                    AHDSR::D => {
-                        if self.params.decay.smoothed.next() < EPSILON {
-                            voice.ahdsr = AHDSR::S;
-                            voice.envelope = self.params.sustain.smoothed.next();
-                        } else {
-                            voice.envelope -= 1.0 / (self.sample_rate * self.params.decay.smoothed.next());
-                            if voice.envelope <= self.params.sustain.smoothed.next() {
-                                voice.ahdsr = AHDSR::S;
-                            }
-                        }
+            }
+            if block.noisy {
+                for (state, noise) in self.partial_noise[..active_harmonics].iter_mut().zip(&block.bandwidth_noise) {
+                    if *noise > 0.0 {
+                        *state += block.bandwidth_lowpass * (self.noise.white() - *state);
                     }
-                    AHDSR::S => {
+                }
+            }
+            let partial_noise = &self.partial_noise;
+            let bandwidth = |i: usize| block.bandwidth_carrier[i] + block.bandwidth_noise[i] * partial_noise[i];
+            let partial_envelopes = &self.partial_envelopes;
+            let level = |i: usize| if block.uniform_envelope { 1.0 } else { partial_envelopes[i].level };
+            let morph = (block.morph[n] + block.velocity_morph * self.velocity).clamp(0.0, 1.0);
+            let (a, b) = (&block.coefficients[n], &block.coefficients_b[n]);
+            let coefficient = |i: usize| a[i] + (b[i] - a[i]) * morph;
+
+            let first = block.active_harmonics + 1;
+            let odd_even = block.odd_even[n];
+            let shaped = block.decay_tilt > 1.0 || odd_even != 0.0 || filter.is_some() || formants.is_some() || darkening > 0.0;
+            // Combs, notches and formants have to be followed partial by partial all the way up.
+            let smooth = filter.is_none() && formants.is_none();
+            let (master, clock) = (&self.envelope, &self.clock);
+            let tail_level = |k: usize| {
+                let level = if block.decay_tilt > 1.0 {
+                    clock.level(master, block.decay_tilt.powi(k as i32 - 1), &block.envelope)
+                } else {
+                    master.level
+                };
+                level * odd_even_gain(k, odd_even) * spectral_gain(k as f32 * freq) * (k as f32).powf(-darkening)
+            };
+
+            // Phase offset of the main oscillator in cycles, shared by the unison copies.
+            let modulation = if block.fm_source != FmSource::Off {
+                let feedback = block.fm_feedback * consts::PI * 0.5 * (self.fm_output[0] + self.fm_output[1]);
+                let modulator_freq = freq * block.fm_ratio * block.phase_mod[n];
+                let modulator = if block.fm_source == FmSource::Layer {
+                    let limit = BandLimit::new(modulator_freq, sample_rate, block.nyquist_rolloff);
+                    let coefficients = &block.layer_coefficients[n];
+                    oscillator::sine_series(
+                        &block.osc_mode,
+                        1..(active_harmonics + 1).min(limit.end()),
+                        self.fm_phase + feedback / consts::TAU,
+                        |k| coefficients[k - 1] * limit.gain(k),
+                    )
+                } else {
+                    (self.fm_phase * consts::TAU + feedback).sin()
+                };
+                self.fm_output = [modulator, self.fm_output[0]];
+                self.fm_phase += modulator_freq / sample_rate;
+                self.fm_phase -= self.fm_phase.floor();
+                block.fm_index[n] * self.fm_envelope.level * modulator / consts::TAU
+            } else {
+                0.0
+            };
 
+            let distortion = block.harmonic && block.phase_distortion != PhaseDistortion::Off;
+            let distortion_amount = block.pd_amount[n] * self.pd_envelope.level;
+            // The warp reads the bank this many times faster at its steepest, so the partials are
+            // limited as if the fundamental were that much higher.
+            let speed = if distortion { distortion::max_slope(block.phase_distortion, distortion_amount) } else { 1.0 };
+            let rolloff = if distortion { block.nyquist_rolloff.max(distortion::MIN_ROLLOFF) } else { block.nyquist_rolloff };
+
+            let (mut left_sum, mut right_sum) = (0.0, 0.0);
+            for (oscillator, (detune, unison_pan)) in self.oscillators[..block.unison]
+                .iter_mut()
+                .zip(block.unison_detune.iter().zip(&block.unison_pan))
+            {
+                let freq = freq * detune;
+                let phase_delta = freq * block.phase_mod[n] / sample_rate;
+                let limit = BandLimit::new(freq * block.phase_mod[n] * speed, sample_rate, rolloff);
+                // The tail, and the whole bank unless the partials are panned, sit at the copy's pan.
+                let oscillator_pan = unison_pan + voice_pan;
+                let (left_gain, right_gain) = pan_gains(oscillator_pan);
+                let partial_pan = |i: usize| pan_gains(block.pans[i] + oscillator_pan);
+                let phase = oscillator.phase + modulation;
+                let (phase, window) = if distortion {
+                    distortion::warp(block.phase_distortion, phase.rem_euclid(1.0), distortion_amount)
+                } else {
+                    (phase, 1.0)
+                };
+                let (bank_left, bank_right) = if block.harmonic {
+                    let partials = 1..(active_harmonics + 1).min(limit.end());
+                    let amplitude = |k: usize| {
+                        let amplitude = coefficient(k - 1) * spectral_gains[k - 1] * bandwidth(k - 1) * limit.gain(k) * level(k - 1);
+                        let (cos, sin) = block.phase_offsets[k - 1];
+                        (amplitude * cos, amplitude * sin)
+                    };
+                    if block.panned {
+                        oscillator::quadrature_series_stereo(&block.osc_mode, partials, phase, amplitude, |k| partial_pan(k - 1))
+                    } else {
+                        let bank = oscillator::quadrature_series(&block.osc_mode, partials, phase, amplitude);
+                        (bank * left_gain, bank * right_gain)
                     }
-                    AHDSR::R => {
-                        if self.params.release.smoothed.next() < EPSILON {
-                            voice.envelope = 0.0;
-                            kill = true;
-                        } else {
-                            voice.envelope -= 1.0 / (self.sample_rate * self.params.release.smoothed.next());
-                            if voice.envelope <= 0.0 {
-                                voice.envelope = 0.0;
-                                kill = true;
-                            }
+                } else {
+                    // Every partial is modulated in proportion to its ratio, like the harmonics are.
+                    let mut modulated = [0.0; HARMONICS_COUNT];
+                    let phases = if modulation != 0.0 {
+                        for ((modulated, phase), ratio) in modulated.iter_mut().zip(&oscillator.partial_phases[..active_harmonics]).zip(&block.ratios) {
+                            *modulated = phase + ratio * modulation;
                         }
+                        &modulated[..active_harmonics]
+                    } else {
+                        &oscillator.partial_phases[..active_harmonics]
+                    };
+                    let amplitude = |i: usize| {
+                        let amplitude = coefficient(i) * spectral_gains[i] * bandwidth(i) * limit.gain_at(block.ratios[i]) * level(i);
+                        let (cos, sin) = block.phase_offsets[i];
+                        (amplitude * cos, amplitude * sin)
+                    };
+                    let wave = if block.panned {
+                        oscillator::partial_bank_stereo(&block.osc_mode, phases, amplitude, partial_pan)
+                    } else {
+                        let bank = oscillator::partial_bank(&block.osc_mode, phases, amplitude);
+                        (bank * left_gain, bank * right_gain)
+                    };
+                    for (partial_phase, ratio) in oscillator.partial_phases[..active_harmonics].iter_mut().zip(&block.ratios) {
+                        *partial_phase += ratio * phase_delta;
+                        *partial_phase -= partial_phase.floor();
                     }
-                    AHDSR::DEAD => {
-                        if self.params.release.smoothed.next() < EPSILON {
-                            voice.envelope = 0.0;
-                            kill = true;
-                        } else {
-                            voice.envelope -= 1.0 / (self.sample_rate * self.params.release.smoothed.next());
-                            if voice.envelope <= 0.0 {
-                                voice.envelope = 0.0;
-                                kill = true;
-                            }
-                        }
+                    wave
+                };
+                // The plain tail always follows the master envelope, the shaped one carries its
+                // own per-partial levels.
+                let phase = phase.rem_euclid(1.0);
+                let tail = if shaped {
+                    let levels = if smooth { TailLevels::Smooth(&tail_level) } else { TailLevels::Exact(&tail_level) };
+                    block.higher_waveform.tail(&block.osc_mode, first, phase, phase_delta * speed, &limit, levels)
+                } else {
+                    block.higher_waveform.tail(&block.osc_mode, first, phase, phase_delta * speed, &limit, TailLevels::Flat) * master.level
+                };
+                let bank_level = if block.uniform_envelope { master.level } else { 1.0 };
+                oscillator.phase += phase_delta;
+                if oscillator.phase >= 1.0 {
+                    oscillator.phase -= 1.0;
+                }
+                left_sum += (bank_left * bank_level + tail * left_gain) * window;
+                right_sum += (bank_right * bank_level + tail * right_gain) * window;
+            }
+            // The envelopes are plain gains, so modulating the enveloped sum is the same as
+            // modulating before them.
+            if block.ring_mode != RingMode::Off {
+                let carrier = (self.ring_phase * consts::TAU).sin();
+                let carrier = if block.ring_mode == RingMode::AM { 0.5 + 0.5 * carrier } else { carrier };
+                let gain = 1.0 - block.ring_mix[n] + block.ring_mix[n] * carrier;
+                left_sum *= gain;
+                right_sum *= gain;
+                let ring_freq = match block.ring_tracking {
+                    RingTracking::Note => self.midi_note_freq * block.ring_freq_factor * block.phase_mod[n],
+                    RingTracking::Fixed => block.ring_freq[n],
+                };
+                self.ring_phase += ring_freq / sample_rate;
+                self.ring_phase -= self.ring_phase.floor();
+            }
+            let (main_gain, layer_gain) = pan_gains(2.0 * block.layer_mix[n] - 1.0);
+            let mut wave = [left_sum * block.unison_gain * main_gain, right_sum * block.unison_gain * main_gain];
+            // The layer and the sub follow the master envelope and sit at the voice's pan.
+            let (left_gain, right_gain) = pan_gains(voice_pan);
+            if block.layer_mix[n] > 0.0 {
+                let layer_freq = self.midi_note_freq * block.layer_freq_factor * block.phase_mod[n];
+                let limit = BandLimit::new(layer_freq, sample_rate, block.nyquist_rolloff);
+                let coefficients = &block.layer_coefficients[n];
+                let layer = oscillator::sine_series(
+                    &block.osc_mode,
+                    1..(active_harmonics + 1).min(limit.end()),
+                    self.layer_phase,
+                    |k| coefficients[k - 1] * limit.gain(k),
+                ) * layer_gain * self.envelope.level;
+                wave[0] += layer * left_gain;
+                wave[1] += layer * right_gain;
+                self.layer_phase += layer_freq / sample_rate;
+                self.layer_phase -= self.layer_phase.floor();
+            }
+            if block.sub_level[n] > 0.0 {
+                let sub = block.sub_level[n] * self.envelope.level * (self.sub_phase * consts::TAU).sin();
+                wave[0] += sub * left_gain;
+                wave[1] += sub * right_gain;
+                self.sub_phase += freq * block.sub_factor * block.phase_mod[n] / sample_rate;
+                self.sub_phase -= self.sub_phase.floor();
+            }
+            if block.noise > EPSILON {
+                let noise = block.noise * self.noise_envelope.level * self.noise.next(block.noise_color, freq, sample_rate);
+                wave[0] += noise * self.envelope.level * left_gain;
+                wave[1] += noise * self.envelope.level * right_gain;
+            }
+
+            self.filter_envelope.next(&block.filter_envelope);
+            if block.filter_mode != FilterMode::Off {
+                let cutoff = block.filter_cutoff[n]
+                    * (freq / 440.0).powf(block.filter_keytrack)
+                    * 2f32.powf(block.filter_env_amount * self.filter_envelope.level + block.filter_velocity * self.velocity);
+                let coefficients = SvfCoefficients::new(cutoff, block.filter_resonance, sample_rate);
+                let stages = if block.filter_slope == FilterSlope::Db24 { 2 } else { 1 };
+                for (wave, filter) in wave.iter_mut().zip(self.filter.iter_mut()) {
+                    for filter in filter[..stages].iter_mut() {
+                        *wave = filter.process(*wave, block.filter_mode, &coefficients);
                     }
                 }
-                final_wave += wave * voice.envelope * lfo_gain_mod;
             }
-            if kill {
-                voice_queue.pop_back();
+            let pressure = self.midi_note_gain.next();
+            let gain = velocity_gain * (1.0 - block.pressure_depth + block.pressure_depth * pressure);
+            *left += wave[0] * block.gain_mod[n] * gain;
+            *right += wave[1] * block.gain_mod[n] * gain;
+            if !alive {
+                return false;
             }
         }
-        final_wave
+        true
+    }
+
+    fn set_stage(&mut self, ahdsr: AHDSR) {
+        if !matches!(self.envelope.ahdsr, AHDSR::R | AHDSR::DEAD) {
+            self.clock.release_level = self.envelope.level;
+        }
+        self.envelope.ahdsr = ahdsr;
+        self.filter_envelope.ahdsr = ahdsr;
+        self.fm_envelope.ahdsr = ahdsr;
+        self.pd_envelope.ahdsr = ahdsr;
+        for state in self.partial_envelopes.iter_mut() {
+            state.ahdsr = ahdsr;
+        }
+    }
+}
+
+impl Seriessynth {
+    fn handle_event(&mut self, event: PluginNoteEvent<Self>) {
+        match event {
+            NoteEvent::NoteOn { note, velocity, .. } => {
+
+                // If the note is already playing, begin the kill phase.
+                if let Some(voice) = self.voices.get_mut(&note).and_then(|queue| queue.front_mut()) {
+                    voice.set_stage(AHDSR::DEAD);
+                    voice.dead = 0.0;
+                }
+                let random_phase = self.params.unison_phase.value();
+                let voice = Voice::new(note, velocity, self.rng.next_u32(), random_phase);
+                let queue = self.voices.entry(note).or_default();
+                queue.push_front(voice);
+            }
+            NoteEvent::NoteOff { note, .. } => {
+                if let Some(voice) = self.voices.get_mut(&note).and_then(|queue| queue.front_mut()) {
+                    voice.set_stage(AHDSR::R);
+                }
+            }
+            NoteEvent::PolyPressure { note, pressure, .. } => {
+                if let Some(voice) = self.voices.get_mut(&note).and_then(|queue| queue.front_mut()) {
+                    voice.midi_note_gain.set_target(self.sample_rate, pressure);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn render_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let block_len = left.len();
+        self.block.update(&self.params, &mut self.lfo_phase, self.sample_rate, block_len);
+        for voice_queue in self.voices.values_mut() {
+            voice_queue.retain_mut(|voice| voice.render(&self.block, self.sample_rate, left, right));
+        }
+
+        let mut gain = [0.0; MAX_BLOCK_SIZE];
+        self.params.gain.smoothed.next_block(&mut gain, block_len);
+        for (((left, right), gain), width) in left.iter_mut().zip(right.iter_mut()).zip(gain).zip(self.block.width) {
+            let gain = util::db_to_gain_fast(gain);
+            let mid = 0.5 * (*left + *right);
+            let side = 0.5 * (*left - *right) * width;
+            *left = (mid + side) * gain;
+            *right = (mid - side) * gain;
+        }
     }
 }
 
@@ -486,16 +2295,44 @@ impl Plugin for Seriessynth {
 
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
         self.sample_rate = buffer_config.sample_rate;
+        self.block.mono = audio_io_layout.main_output_channels == NonZeroU32::new(1);
         true
     }
 
     fn reset(&mut self) {
         self.voices.clear();
+        self.lfo_phase = 0.0;
+        // Outside deterministic mode the next seed comes from the current stream, since the
+        // thread RNG is not safe to call on the audio thread.
+        self.rng = if self.params.deterministic.value() {
+            Rng::new(self.params.seed.value() as u32)
+        } else {
+            Rng::new(self.rng.next_u32())
+        };
+    }
+
+    fn filter_state(state: &mut PluginState) {
+        // Older projects picked the harmonic slope from 1, 1/N and 1/N^2.
+        if let Some(ParamValue::String(amp_width)) = state.params.remove("ampwidth") {
+            let tilt = match amp_width.as_str() {
+                "N" => 1.0,
+                "N2" => 2.0,
+                _ => 0.0,
+            };
+            state.params.entry(String::from("tilt")).or_insert(ParamValue::F32(tilt));
+        }
+        // Projects from before velocity sensitivity played every note at the same level.
+        state.params.entry(String::from("velocitydepth")).or_insert(ParamValue::F32(0.0));
+        // Projects from before the oscillator modes rendered every partial exactly.
+        state
+            .params
+            .entry(String::from("oscmode"))
+            .or_insert(ParamValue::I32(OscMode::Exact.to_index() as i32));
     }
 
     fn process(
@@ -504,78 +2341,44 @@ impl Plugin for Seriessynth {
         _aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
+        let num_samples = buffer.samples();
+        let output = buffer.as_slice();
+
+        // Render in blocks of up to `MAX_BLOCK_SIZE` samples, split at every note event.
         let mut next_event = context.next_event();
-        for (sample_id, channel_samples) in buffer.iter_samples().enumerate() {
-            let gain = self.params.gain.smoothed.next();
-
-            let output_sample;
-            let _sine =  {
-                while let Some(event) = next_event {
-                    if event.timing() > sample_id as u32 {
-                        break;
+        let mut block_start: usize = 0;
+        let mut block_end: usize = MAX_BLOCK_SIZE.min(num_samples);
+        while block_start < num_samples {
+            'events: loop {
+                match next_event {
+                    Some(event) if (event.timing() as usize) <= block_start => {
+                        self.handle_event(event);
+                        next_event = context.next_event();
                     }
-
-                    match event {
-                        NoteEvent::NoteOn { note, velocity, .. } => {
-                            
-                            // If the note is already playing, begin the kill phase.
-                            match self.voices.get_mut(&note) {
-                                Some(voice_queue) => {
-                                    let voice = voice_queue.get_mut(0);
-                                    match voice {
-                                        Some(voice) => {
-                                            voice.ahdsr = AHDSR::DEAD;
-                                            voice.dead = 0.0;
-                                        }
-                                        None => (),
-                                    }
-                                }
-                                None => (),
-                            }
-                            let voice = Voice {
-                                phase: 0.0,
-                                midi_note_freq: util::midi_note_to_freq(note),
-                                midi_note_gain: Smoother::new(SmoothingStyle::Linear(5.0)),
-                                ahdsr: AHDSR::A,
-                                envelope: 0.0,
-                                hold: 0.0,
-                                dead: 0.0,
-                            };
-                            voice.midi_note_gain.set_target(self.sample_rate, velocity);
-                            let queue = self.voices.entry(note).or_insert_with(VecDeque::new);
-                            queue.push_front(voice);
-                        }
-                        NoteEvent::NoteOff { note, .. } => {
-                            if let Some(voice_queue) = self.voices.get_mut(&note) {
-                                voice_queue.get_mut(0).unwrap().ahdsr = AHDSR::R;
-                            }
-                        }
-                        NoteEvent::PolyPressure { note, pressure, .. } => {
-                            if let Some(voice_queue) = self.voices.get_mut(&note) {
-                                match voice_queue.get_mut(0) {
-                                    Some(voice) => {
-                                        voice.midi_note_gain.set_target(self.sample_rate, pressure);
-                                    }
-                                    None => (),
-                                }
-                            }
-                        }
-                        _ => (),
+                    Some(event) if (event.timing() as usize) < block_end => {
+                        block_end = event.timing() as usize;
+                        break 'events;
                     }
-
-                    next_event = context.next_event();
+                    _ => break 'events,
                 }
+            }
 
-                // for voice in self.voices.values_mut() {
-                //     // output_sample += voice.calculate(self.sample_rate, &series, &ahdsr) * voice.midi_note_gain.next();
-                //     output_sample += self.calculate(voice) * voice.midi_note_gain.next();
-                // }
-                output_sample = self.calculate() * util::db_to_gain_fast(gain);
-            };
-
-            for sample in channel_samples {
-                *sample = output_sample;
+            let block_len = block_end - block_start;
+            let mut left = [0.0; MAX_BLOCK_SIZE];
+            let mut right = [0.0; MAX_BLOCK_SIZE];
+            self.render_block(&mut left[..block_len], &mut right[..block_len]);
+            match output {
+                // Both channels carry the same unpanned signal when the layout is mono.
+                [mono] => mono[block_start..block_end].copy_from_slice(&left[..block_len]),
+                [left_channel, right_channel, ..] => {
+                    left_channel[block_start..block_end].copy_from_slice(&left[..block_len]);
+                    right_channel[block_start..block_end].copy_from_slice(&right[..block_len]);
+                }
+                [] => (),
             }
+
+            block_start = block_end;
+            block_end = (block_start + MAX_BLOCK_SIZE).min(num_samples);
         }
 
         ProcessStatus::KeepAlive
@@ -607,3 +2410,281 @@ impl Vst3Plugin for Seriessynth {
 
 nih_export_clap!(Seriessynth);
 nih_export_vst3!(Seriessynth);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use oscillator::{blep_response, BLEP_MIN_PARTIALS};
+
+    pub(crate) const SAMPLE_RATE: f32 = 48000.0;
+    // 10 Hz bins, so every multiple of 10 Hz lands exactly on a bin.
+    pub(crate) const LENGTH: usize = 4800;
+
+    pub(crate) fn block(harmonics: f32, higher_waveform: Waveform, osc_mode: OscMode, rolloff: f32) -> Block {
+        let mut block = Block::default();
+        for coefficients in block.coefficients.iter_mut() {
+            coefficients.fill(harmonics);
+        }
+        block.higher_waveform = higher_waveform;
+        block.osc_mode = osc_mode;
+        block.nyquist_rolloff = rolloff;
+        block
+    }
+
+    pub(crate) fn render(freq: f32, harmonics: f32, higher_waveform: Waveform, osc_mode: OscMode, rolloff: f32) -> Vec<f32> {
+        render_block(&block(harmonics, higher_waveform, osc_mode, rolloff), freq)
+    }
+
+    pub(crate) fn render_block(block: &Block, freq: f32) -> Vec<f32> {
+        let mut voice = Voice::new(69, 1.0, 1, 0.0);
+        voice.midi_note_freq = freq;
+        let mut output = vec![0.0; LENGTH];
+        let mut right = [0.0; MAX_BLOCK_SIZE];
+        for chunk in output.chunks_mut(MAX_BLOCK_SIZE) {
+            assert!(voice.render(block, SAMPLE_RATE, chunk, &mut right[..chunk.len()]));
+        }
+        output
+    }
+
+    pub(crate) fn bin_energy(signal: &[f32], freq: f32) -> f64 {
+        let omega = std::f64::consts::TAU * freq as f64 / SAMPLE_RATE as f64;
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, x) in signal.iter().enumerate() {
+            re += *x as f64 * (omega * n as f64).cos();
+            im -= *x as f64 * (omega * n as f64).sin();
+        }
+        let scale = if freq == 0.0 { 1.0 } else { 2.0 };
+        scale * (re * re + im * im) / (signal.len() as f64 * signal.len() as f64)
+    }
+
+    // Energy in the 10 Hz bins below `limit` that are not harmonics of `freq`, relative to the
+    // whole signal. The PolyBLEP waveforms alias mostly near Nyquist, where this does not look.
+    fn aliased_energy_below(signal: &[f32], freq: f32, limit: f32) -> f64 {
+        let total = signal.iter().map(|x| *x as f64 * *x as f64).sum::<f64>() / signal.len() as f64;
+        if total == 0.0 {
+            return 0.0;
+        }
+        let mut aliased = 0.0;
+        let mut bin = 10.0;
+        while bin < limit {
+            if (bin / freq).fract() != 0.0 {
+                aliased += bin_energy(signal, bin);
+            }
+            bin += 10.0;
+        }
+        aliased / total
+    }
+
+    fn state(params: &[(&str, ParamValue)]) -> PluginState {
+        PluginState {
+            version: String::new(),
+            params: params.iter().map(|(id, value)| (id.to_string(), value.clone())).collect(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn old_projects_map_amp_width_to_tilt() {
+        for (amp_width, tilt) in [("1", 0.0), ("N", 1.0), ("N2", 2.0)] {
+            let mut old = state(&[("ampwidth", ParamValue::String(amp_width.to_string()))]);
+            Seriessynth::filter_state(&mut old);
+            assert!(!old.params.contains_key("ampwidth"));
+            assert!(matches!(old.params.get("tilt"), Some(ParamValue::F32(value)) if *value == tilt));
+        }
+
+        let mut both = state(&[
+            ("ampwidth", ParamValue::String("N2".to_string())),
+            ("tilt", ParamValue::F32(0.5)),
+        ]);
+        Seriessynth::filter_state(&mut both);
+        assert!(matches!(both.params.get("tilt"), Some(ParamValue::F32(value)) if *value == 0.5));
+    }
+
+    #[test]
+    fn old_projects_keep_the_exact_oscillator() {
+        let mut old = state(&[]);
+        Seriessynth::filter_state(&mut old);
+        assert!(matches!(old.params.get("oscmode"), Some(ParamValue::I32(0))));
+
+        let mut fast = state(&[("oscmode", ParamValue::I32(1))]);
+        Seriessynth::filter_state(&mut fast);
+        assert!(matches!(fast.params.get("oscmode"), Some(ParamValue::I32(1))));
+    }
+
+    #[test]
+    fn old_projects_ignore_velocity() {
+        let mut old = state(&[]);
+        Seriessynth::filter_state(&mut old);
+        assert!(matches!(old.params.get("velocitydepth"), Some(ParamValue::F32(depth)) if *depth == 0.0));
+
+        let mut sensitive = state(&[("velocitydepth", ParamValue::F32(0.7))]);
+        Seriessynth::filter_state(&mut sensitive);
+        assert!(matches!(sensitive.params.get("velocitydepth"), Some(ParamValue::F32(depth)) if *depth == 0.7));
+    }
+
+    #[test]
+    fn neighbouring_seeds_differ() {
+        let (mut zero, mut one) = (Rng::new(0), Rng::new(1));
+        assert!((0..16).any(|_| zero.next_u32() != one.next_u32()));
+    }
+
+    #[test]
+    fn free_running_resets_reseed() {
+        let mut synth = Seriessynth::default();
+        synth.reset();
+        let first = synth.rng.next_u32();
+        synth.reset();
+        assert_ne!(first, synth.rng.next_u32());
+    }
+
+    #[test]
+    fn deterministic_renders_are_bit_identical() {
+        let mut params = SeriessynthParams {
+            deterministic: BoolParam::new("Deterministic", true),
+            seed: IntParam::new("Seed", 1234, IntRange::Linear { min: 0, max: 65535 }),
+            noise: FloatParam::new("Noise", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            unison: IntParam::new("Unison", 3, IntRange::Linear { min: 1, max: MAX_UNISON as i32 }),
+            unison_phase: FloatParam::new("Unison phase", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            ..SeriessynthParams::default()
+        };
+        params.harmonics[0].bandwidth = FloatParam::new("Bandwidth", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 });
+        let mut synth = Seriessynth {
+            params: Arc::new(params),
+            ..Seriessynth::default()
+        };
+
+        let mut render = || {
+            synth.reset();
+            for note in [60, 64, 67] {
+                synth.handle_event(NoteEvent::NoteOn {
+                    timing: 0,
+                    voice_id: None,
+                    channel: 0,
+                    note,
+                    velocity: 0.8,
+                });
+            }
+            let mut output = Vec::new();
+            for _ in 0..16 {
+                let (mut left, mut right) = ([0.0; MAX_BLOCK_SIZE], [0.0; MAX_BLOCK_SIZE]);
+                synth.render_block(&mut left, &mut right);
+                output.extend(left.iter().chain(&right).map(|sample| sample.to_bits()));
+            }
+            output
+        };
+        let first = render();
+        assert!(first.iter().any(|&sample| f32::from_bits(sample) != 0.0));
+        assert_eq!(first, render());
+    }
+
+    #[test]
+    fn tilted_tail_starts_its_decay_at_the_top() {
+        let clock = DecayClock {
+            decay_samples: 0.0,
+            release_samples: 0.0,
+            release_level: 0.0,
+        };
+        let master = EnvelopeState {
+            ahdsr: AHDSR::D,
+            level: 1.0,
+            hold: 0.0,
+        };
+        assert_eq!(clock.level(&master, 4.0, &Block::default().envelope), 1.0);
+    }
+
+    // These notes take the PolyBLEP path and fold their partials back between the harmonics, where
+    // the aliasing can be seen.
+    const BLEP_FREQS: [f32; 3] = [70.0, 110.0, 130.0];
+
+    #[test]
+    fn blep_tail_does_not_alias() {
+        for waveform in [Waveform::Sawtooth, Waveform::Square, Waveform::Triangle] {
+            for freq in BLEP_FREQS {
+                let signal = render(freq, 0.0, waveform, OscMode::Fast, 0.0);
+                let aliased = aliased_energy_below(&signal, freq, SAMPLE_RATE / 4.0);
+                assert!(aliased < 2e-4, "{freq} Hz: {aliased}");
+            }
+        }
+    }
+
+    #[test]
+    fn fast_tail_droops_like_the_blep() {
+        for waveform in [Waveform::Sawtooth, Waveform::Square, Waveform::Triangle] {
+            // The last two are short enough for the additive tail.
+            for freq in BLEP_FREQS.into_iter().chain([220.0, 330.0]) {
+                let exact = render(freq, 0.0, waveform, OscMode::Exact, 0.0);
+                let fast = render(freq, 0.0, waveform, OscMode::Fast, 0.0);
+                let first = bin_energy(&exact, 33.0 * freq);
+                let mut k = 32;
+                while k as f32 * freq < SAMPLE_RATE / 2.0 {
+                    let partial = k as f32 * freq;
+                    // Skips the square's and triangle's even partials.
+                    if bin_energy(&exact, partial) > 1e-4 * first {
+                        let gain = (bin_energy(&fast, partial) / bin_energy(&exact, partial)).sqrt();
+                        let expected = blep_response(partial / SAMPLE_RATE) as f64;
+                        assert!((gain - expected).abs() < 5e-3, "{freq} Hz, partial {k}: {gain} against {expected}");
+                    }
+                    k += 1;
+                }
+            }
+        }
+    }
+
+    fn render_tail(waveform: Waveform, freq: f32, level: Option<&dyn Fn(usize) -> f32>) -> Vec<f32> {
+        let limit = BandLimit::new(freq, SAMPLE_RATE, 0.0);
+        let phase_delta = freq / SAMPLE_RATE;
+        (0..LENGTH)
+            .map(|n| {
+                let levels = level.map_or(TailLevels::Flat, TailLevels::Smooth);
+                waveform.tail(&OscMode::Fast, 32, (n as f32 * phase_delta).fract(), phase_delta, &limit, levels)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shaped_blep_tail_follows_its_level() {
+        let split = 32 + BLEP_MIN_PARTIALS;
+        let darkening = |k: usize| 32.0 / k as f32;
+        let balanced = |k: usize| if k % 2 == 1 { 0.25 } else { 32.0 / k as f32 };
+        let even_only = |k: usize| if k % 2 == 1 { 0.0 } else { 1.0 };
+        let levels: [&dyn Fn(usize) -> f32; 3] = [&darkening, &balanced, &even_only];
+        for level in levels {
+            for waveform in [Waveform::Sawtooth, Waveform::Square, Waveform::Triangle] {
+                for freq in BLEP_FREQS {
+                    let plain = render_tail(waveform, freq, None);
+                    let shaped = render_tail(waveform, freq, Some(level));
+                    let aliased = aliased_energy_below(&shaped, freq, SAMPLE_RATE / 4.0);
+                    assert!(aliased < 2e-4, "{freq} Hz: {aliased}");
+                    let first = bin_energy(&plain, 33.0 * freq);
+                    let mut k = 32;
+                    while k as f32 * freq < SAMPLE_RATE / 2.0 {
+                        let partial = k as f32 * freq;
+                        if bin_energy(&plain, partial) > 1e-4 * first {
+                            let gain = (bin_energy(&shaped, partial) / bin_energy(&plain, partial)).sqrt();
+                            // Partials above the split sit at the level of the split's odd or even
+                            // neighbour.
+                            let held = if k < split { k } else if k % 2 == 1 { split | 1 } else { split + split % 2 };
+                            let expected = level(held) as f64;
+                            assert!((gain - expected).abs() < 5e-3, "{freq} Hz, partial {k}: {gain} against {expected}");
+                        }
+                        k += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blep_tail_rolls_off() {
+        // The second roll-off spans more partials than the BLEP takes off one at a time.
+        for (freq, rolloff, partials) in [(110.0, 0.2, [150, 180, 200, 215]), (70.0, 0.5, [150, 230, 280, 330])] {
+            let full = render(freq, 0.0, Waveform::Sawtooth, OscMode::Fast, 0.0);
+            let faded = render(freq, 0.0, Waveform::Sawtooth, OscMode::Fast, rolloff);
+            let limit = BandLimit::new(freq, SAMPLE_RATE, rolloff).with_rolloff_partials(BLEP_MIN_PARTIALS);
+            for k in partials {
+                let gain = (bin_energy(&faded, k as f32 * freq) / bin_energy(&full, k as f32 * freq)).sqrt();
+                assert!((gain - limit.gain(k) as f64).abs() < 5e-3, "{freq} Hz, partial {k}: {gain}");
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use nih_plug::prelude::*;
+use std::f32::consts;
+
+use crate::svf::{self, FilterMode, Svf, SvfCoefficients};
+
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+pub(crate) enum NoiseColor {
+    White,
+    Pink,
+    Brown,
+    // White noise through a bandpass centred on the note.
+    Band,
+}
+
+const BAND_RESONANCE: f32 = 0.5;
+
+// xorshift32, cheap enough for every sample and free of locks and allocations.
+#[derive(Clone, Copy)]
+pub(crate) struct Rng(u32);
+
+impl Rng {
+    pub(crate) fn new(seed: u32) -> Self {
+        // Neighbouring seeds are scattered with the murmur3 finaliser, so that each one starts its
+        // own stream. Zero is the one state xorshift never leaves, and only a seed far outside
+        // the Seed parameter's range lands there.
+        let mut x = seed.wrapping_add(0x9e37_79b9);
+        x ^= x >> 16;
+        x = x.wrapping_mul(0x85eb_ca6b);
+        x ^= x >> 13;
+        x = x.wrapping_mul(0xc2b2_ae35);
+        x ^= x >> 16;
+        Self(x.max(1))
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    // Uniform in [-1, 1).
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 23) as f32 - 1.0
+    }
+}
+
+pub(crate) struct Noise {
+    rng: Rng,
+    pink: [f32; 3],
+    brown: f32,
+    band: Svf,
+}
+
+impl Noise {
+    pub(crate) fn new(seed: u32) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            pink: [0.0; 3],
+            brown: 0.0,
+            band: Svf::default(),
+        }
+    }
+
+    pub(crate) fn white(&mut self) -> f32 {
+        self.rng.next_f32()
+    }
+
+    // One sample at roughly the level of the white noise.
+    pub(crate) fn next(&mut self, color: NoiseColor, freq: f32, sample_rate: f32) -> f32 {
+        let white = self.rng.next_f32();
+        match color {
+            NoiseColor::White => white,
+            // Paul Kellet's economy pinking filter.
+            NoiseColor::Pink => {
+                self.pink[0] = 0.99765 * self.pink[0] + white * 0.0990460;
+                self.pink[1] = 0.96300 * self.pink[1] + white * 0.2965164;
+                self.pink[2] = 0.57000 * self.pink[2] + white * 1.0526913;
+                (self.pink[0] + self.pink[1] + self.pink[2] + white * 0.1848) * 0.33
+            }
+            NoiseColor::Brown => {
+                self.brown = (self.brown + 0.02 * white) / 1.02;
+                self.brown * 10.0
+            }
+            NoiseColor::Band => {
+                let coefficients = SvfCoefficients::new(freq, BAND_RESONANCE, sample_rate);
+                // Make up for the share of the band the filter lets through, π f / 2Q out of
+                // Nyquist, at the centre the filter really has.
+                let centre = svf::clamp_cutoff(freq, sample_rate);
+                let makeup = (sample_rate * svf::q(BAND_RESONANCE) / (consts::PI * centre)).sqrt();
+                self.band.process(white, FilterMode::BandPass, &coefficients) * makeup
+            }
+        }
+    }
+}
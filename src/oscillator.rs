@@ -0,0 +1,222 @@
+use std::f32::consts;
+use std::ops::Range;
+
+use crate::OscMode;
+
+// Sum of `amplitude(k) * sin(k * phase)` for every partial `k` in `partials`, with `phase` in cycles.
+pub(crate) fn sine_series(
+    mode: &OscMode,
+    partials: Range<usize>,
+    phase: f32,
+    amplitude: impl Fn(usize) -> f32,
+) -> f32 {
+    match mode {
+        OscMode::Exact => partials
+            .map(|k| amplitude(k) * ((k as f32) * phase * consts::TAU).sin())
+            .sum(),
+        OscMode::Fast => {
+            if partials.is_empty() {
+                return 0.0;
+            }
+            // Step from partial k to k + 1 by rotating (cos kx, sin kx) by x, so there is only one
+            // sin_cos() for the first partial and one for the step no matter how many partials follow.
+            let (step_sin, step_cos) = (phase * consts::TAU).sin_cos();
+            let (mut sin, mut cos) = ((partials.start as f32) * phase * consts::TAU).sin_cos();
+            let mut sum = 0.0;
+            for k in partials {
+                sum += amplitude(k) * sin;
+                (sin, cos) = (sin * step_cos + cos * step_sin, cos * step_cos - sin * step_sin);
+            }
+            sum
+        }
+    }
+}
+
+// Calls `visit(k, sin(k * phase), cos(k * phase))` for every partial `k` in `partials`.
+fn quadrature_partials(mode: &OscMode, partials: Range<usize>, phase: f32, mut visit: impl FnMut(usize, f32, f32)) {
+    match mode {
+        OscMode::Exact => {
+            for k in partials {
+                let (sin, cos) = ((k as f32) * phase * consts::TAU).sin_cos();
+                visit(k, sin, cos);
+            }
+        }
+        OscMode::Fast => {
+            if partials.is_empty() {
+                return;
+            }
+            let (step_sin, step_cos) = (phase * consts::TAU).sin_cos();
+            let (mut sin, mut cos) = ((partials.start as f32) * phase * consts::TAU).sin_cos();
+            for k in partials {
+                visit(k, sin, cos);
+                (sin, cos) = (sin * step_cos + cos * step_sin, cos * step_cos - sin * step_sin);
+            }
+        }
+    }
+}
+
+// Sum of `a * sin(k * phase) + b * cos(k * phase)` for every partial `k` in `partials`, where
+// `(a, b) = amplitude(k)`. A partial of amplitude r and phase offset φ is `(r cos φ, r sin φ)`.
+pub(crate) fn quadrature_series(
+    mode: &OscMode,
+    partials: Range<usize>,
+    phase: f32,
+    amplitude: impl Fn(usize) -> (f32, f32),
+) -> f32 {
+    let mut sum = 0.0;
+    quadrature_partials(mode, partials, phase, |k, sin, cos| {
+        let (a, b) = amplitude(k);
+        sum += a * sin + b * cos;
+    });
+    sum
+}
+
+// `quadrature_series` into two channels, with every partial weighted by the (left, right) gains
+// from `pan`.
+pub(crate) fn quadrature_series_stereo(
+    mode: &OscMode,
+    partials: Range<usize>,
+    phase: f32,
+    amplitude: impl Fn(usize) -> (f32, f32),
+    pan: impl Fn(usize) -> (f32, f32),
+) -> (f32, f32) {
+    let (mut left, mut right) = (0.0, 0.0);
+    quadrature_partials(mode, partials, phase, |k, sin, cos| {
+        let (a, b) = amplitude(k);
+        let value = a * sin + b * cos;
+        let (left_gain, right_gain) = pan(k);
+        left += value * left_gain;
+        right += value * right_gain;
+    });
+    (left, right)
+}
+
+// Calls `visit(i, value)` with every audible partial of `partial_bank`.
+fn bank_partials(
+    mode: &OscMode,
+    phases: &[f32],
+    amplitude: impl Fn(usize) -> (f32, f32),
+    mut visit: impl FnMut(usize, f32),
+) {
+    for (i, phase) in phases.iter().enumerate() {
+        let (a, b) = amplitude(i);
+        if a == 0.0 && b == 0.0 {
+            continue;
+        }
+        let (sin, cos) = match mode {
+            OscMode::Exact => (phase * consts::TAU).sin_cos(),
+            OscMode::Fast => (fast_sin(*phase), fast_sin(*phase + 0.25)),
+        };
+        visit(i, a * sin + b * cos);
+    }
+}
+
+// Like `quadrature_series`, but every partial runs at its own phase from `phases` (in cycles) and
+// `amplitude` is indexed by position in `phases`. Silent partials are skipped.
+pub(crate) fn partial_bank(
+    mode: &OscMode,
+    phases: &[f32],
+    amplitude: impl Fn(usize) -> (f32, f32),
+) -> f32 {
+    let mut sum = 0.0;
+    bank_partials(mode, phases, amplitude, |_, value| sum += value);
+    sum
+}
+
+pub(crate) fn partial_bank_stereo(
+    mode: &OscMode,
+    phases: &[f32],
+    amplitude: impl Fn(usize) -> (f32, f32),
+    pan: impl Fn(usize) -> (f32, f32),
+) -> (f32, f32) {
+    let (mut left, mut right) = (0.0, 0.0);
+    bank_partials(mode, phases, amplitude, |i, value| {
+        let (left_gain, right_gain) = pan(i);
+        left += value * left_gain;
+        right += value * right_gain;
+    });
+    (left, right)
+}
+
+// sin(2π x) from a 9th order Taylor polynomial on the quarter cycle around zero, within 4e-6.
+fn fast_sin(x: f32) -> f32 {
+    let mut x = x - x.round();
+    if x > 0.25 {
+        x = 0.5 - x;
+    } else if x < -0.25 {
+        x = -0.5 - x;
+    }
+    let x = x * consts::TAU;
+    let x2 = x * x;
+    x * (1.0 - x2 / 6.0 * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0 * (1.0 - x2 / 72.0))))
+}
+
+// Below this many partials the additive tail is cheap enough, and the BLEP oscillators lose too
+// much of the top octave relative to the little that is left of the tail.
+pub(crate) const BLEP_MIN_PARTIALS: usize = 128;
+
+// Two-sample polynomial estimate of the band-limited unit step minus the naive one, for a step at
+// phase 0. `phase` and `phase_delta` are in cycles.
+fn poly_blep(phase: f32, phase_delta: f32) -> f32 {
+    if phase < phase_delta {
+        let x = phase / phase_delta;
+        -(1.0 - x) * (1.0 - x) / 2.0
+    } else if phase > 1.0 - phase_delta {
+        let x = (phase - 1.0) / phase_delta;
+        (x + 1.0) * (x + 1.0) / 2.0
+    } else {
+        0.0
+    }
+}
+
+// Integral of `poly_blep`, for a change of slope of one per sample at phase 0.
+fn poly_blamp(phase: f32, phase_delta: f32) -> f32 {
+    if phase < phase_delta {
+        let x = 1.0 - phase / phase_delta;
+        x * x * x / 6.0
+    } else if phase > 1.0 - phase_delta {
+        let x = (phase - 1.0) / phase_delta + 1.0;
+        x * x * x / 6.0
+    } else {
+        0.0
+    }
+}
+
+// Amplitude of a partial at `freq` cycles per sample in the PolyBLEP waveforms below, relative
+// to the full Fourier series. The polynomials smooth every step like a two-sample triangular
+// kernel, which takes the top octave down by up to 8 dB at Nyquist.
+pub(crate) fn blep_response(freq: f32) -> f32 {
+    if freq <= 0.0 {
+        return 1.0;
+    }
+    let x = consts::PI * freq;
+    (x.sin() / x).powi(2)
+}
+
+// The classic waveforms below are scaled to the sums of their full Fourier series with partials of
+// 1/k (sawtooth, square) and 1/k^2 (triangle), in the same sine phase as `sine_series`.
+
+pub(crate) fn saw(phase: f32, phase_delta: f32) -> f32 {
+    consts::FRAC_PI_2 - consts::PI * phase + consts::PI * poly_blep(phase, phase_delta)
+}
+
+pub(crate) fn square(phase: f32, phase_delta: f32) -> f32 {
+    let naive = if phase < 0.5 { 1.0 } else { -1.0 };
+    consts::FRAC_PI_4
+        * (naive + 2.0 * poly_blep(phase, phase_delta)
+            - 2.0 * poly_blep((phase + 0.5) % 1.0, phase_delta))
+}
+
+pub(crate) fn triangle(phase: f32, phase_delta: f32) -> f32 {
+    let naive = if phase < 0.25 {
+        4.0 * phase
+    } else if phase < 0.75 {
+        2.0 - 4.0 * phase
+    } else {
+        4.0 * phase - 4.0
+    };
+    // The slope turns by -8 per cycle at the peak and by +8 at the trough.
+    consts::PI * consts::PI / 8.0
+        * (naive - 8.0 * phase_delta * poly_blamp((phase + 0.75) % 1.0, phase_delta)
+            + 8.0 * phase_delta * poly_blamp((phase + 0.25) % 1.0, phase_delta))
+}
@@ -0,0 +1,66 @@
+use std::array;
+
+use crate::HARMONICS_COUNT;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PhasePattern {
+    Zero,
+    Alternating,
+    Random,
+    Schroeder,
+}
+
+// Phase offsets in degrees for every harmonic. `amplitudes` are the harmonics' effective
+// amplitudes, which only the Schroeder pattern looks at.
+pub(crate) fn phases(pattern: PhasePattern, amplitudes: &[f32; HARMONICS_COUNT]) -> [f32; HARMONICS_COUNT] {
+    match pattern {
+        PhasePattern::Zero => [0.0; HARMONICS_COUNT],
+        // Odd harmonics in sine phase, even harmonics in cosine phase.
+        PhasePattern::Alternating => array::from_fn(|i| if i % 2 == 0 { 0.0 } else { 90.0 }),
+        PhasePattern::Random => array::from_fn(|_| rand::random_range(0.0..360.0)),
+        PhasePattern::Schroeder => schroeder(amplitudes),
+    }
+}
+
+// Schroeder's low crest factor phases for an arbitrary power spectrum:
+// φ_k = -2π Σ_{l<k} (k - l) p_l, where p_l is harmonic l's share of the total power.
+fn schroeder(amplitudes: &[f32; HARMONICS_COUNT]) -> [f32; HARMONICS_COUNT] {
+    let total: f32 = amplitudes.iter().map(|a| a * a).sum();
+    if total <= 0.0 {
+        return [0.0; HARMONICS_COUNT];
+    }
+    array::from_fn(|k| {
+        let cycles: f32 = amplitudes[..k]
+            .iter()
+            .enumerate()
+            .map(|(l, a)| (k - l) as f32 * a * a / total)
+            .sum();
+        (-cycles * 360.0).rem_euclid(360.0)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PanPattern {
+    Center,
+    OddEven,
+    Spread,
+    Random,
+}
+
+// Pan positions from -1 (left) to 1 (right) for every harmonic. `Spread` sweeps the
+// `active_harmonics` lowest harmonics from left to right.
+pub(crate) fn pans(pattern: PanPattern, active_harmonics: usize) -> [f32; HARMONICS_COUNT] {
+    match pattern {
+        PanPattern::Center => [0.0; HARMONICS_COUNT],
+        // Odd harmonics left, even harmonics right.
+        PanPattern::OddEven => array::from_fn(|i| if i % 2 == 0 { -1.0 } else { 1.0 }),
+        PanPattern::Spread => array::from_fn(|i| {
+            if active_harmonics > 1 {
+                (2.0 * i as f32 / (active_harmonics - 1) as f32 - 1.0).min(1.0)
+            } else {
+                0.0
+            }
+        }),
+        PanPattern::Random => array::from_fn(|_| rand::random_range(-1.0..1.0)),
+    }
+}
@@ -0,0 +1,99 @@
+use nih_plug::prelude::*;
+use std::f32::consts;
+
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+pub(crate) enum SpectralFilterMode {
+    Off,
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+    Comb,
+}
+
+// A filter that is never run over the signal. Its magnitude response is read off at every
+// partial's frequency and multiplied into the partial's amplitude, so it has no state and cannot
+// alias.
+pub(crate) struct SpectralFilter {
+    mode: SpectralFilterMode,
+    cutoff: f32,
+    resonance: f32,
+    q: f32,
+}
+
+impl SpectralFilter {
+    pub(crate) fn new(mode: SpectralFilterMode, cutoff: f32, resonance: f32) -> Self {
+        Self {
+            mode,
+            cutoff,
+            resonance,
+            q: consts::FRAC_1_SQRT_2 + resonance * 10.0,
+        }
+    }
+
+    // The responses of the 12 dB/octave analog prototypes, and for the comb raised cosine peaks at
+    // every multiple of the cutoff that narrow as the resonance goes up.
+    pub(crate) fn gain(&self, freq: f32) -> f32 {
+        let x = freq / self.cutoff;
+        let x2 = x * x;
+        let denominator = ((1.0 - x2) * (1.0 - x2) + x2 / (self.q * self.q)).sqrt();
+        match self.mode {
+            SpectralFilterMode::Off => 1.0,
+            SpectralFilterMode::LowPass => 1.0 / denominator,
+            SpectralFilterMode::HighPass => x2 / denominator,
+            SpectralFilterMode::BandPass => x / self.q / denominator,
+            SpectralFilterMode::Notch => (1.0 - x2).abs() / denominator,
+            SpectralFilterMode::Comb => {
+                let peak = 0.5 + 0.5 * (x * consts::TAU).cos();
+                peak.powf(1.0 + 4.0 * self.resonance)
+            }
+        }
+    }
+}
+
+// First three formants of the vowels a, e, i, o, u as (frequency, amplitude in dB, bandwidth), after
+// the bass voice in the Csound manual's formant table.
+const VOWELS: [[(f32, f32, f32); 3]; 5] = [
+    [(600.0, 0.0, 60.0), (1040.0, -7.0, 70.0), (2250.0, -9.0, 110.0)],
+    [(400.0, 0.0, 40.0), (1620.0, -12.0, 80.0), (2400.0, -9.0, 100.0)],
+    [(250.0, 0.0, 60.0), (1750.0, -30.0, 90.0), (2600.0, -16.0, 100.0)],
+    [(400.0, 0.0, 40.0), (750.0, -11.0, 80.0), (2400.0, -21.0, 100.0)],
+    [(350.0, 0.0, 40.0), (600.0, -20.0, 80.0), (2400.0, -32.0, 100.0)],
+];
+
+// A vowel shaped spectral envelope on absolute frequency. `position` runs from 0 (a) to 4 (u)
+// and crossfades the formants of neighbouring vowels.
+pub(crate) struct Formants {
+    peaks: [(f32, f32, f32); 3],
+    amount: f32,
+}
+
+impl Formants {
+    pub(crate) fn new(position: f32, amount: f32) -> Self {
+        let position = position.clamp(0.0, (VOWELS.len() - 1) as f32);
+        let index = (position as usize).min(VOWELS.len() - 2);
+        let t = position - index as f32;
+        let (from, to) = (VOWELS[index], VOWELS[index + 1]);
+        let mut peaks = [(0.0, 0.0, 0.0); 3];
+        for (peak, (from, to)) in peaks.iter_mut().zip(from.iter().zip(&to)) {
+            let db = from.1 + (to.1 - from.1) * t;
+            *peak = (from.0 + (to.0 - from.0) * t, 10f32.powf(db / 20.0), from.2 + (to.2 - from.2) * t);
+        }
+        Self { peaks, amount }
+    }
+
+    // Sum of one resonance per formant, mixed with the unshaped spectrum by `amount`. The peaks are
+    // twice as wide as the table's bandwidths so that high notes, with few partials near each
+    // formant, still sound like the vowel.
+    pub(crate) fn gain(&self, freq: f32) -> f32 {
+        let envelope: f32 = self
+            .peaks
+            .iter()
+            .map(|(center, amplitude, bandwidth)| {
+                let x = (freq - center) / bandwidth;
+                amplitude / (1.0 + x * x)
+            })
+            .sum();
+        1.0 - self.amount + self.amount * envelope
+    }
+}
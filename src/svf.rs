@@ -0,0 +1,74 @@
+use nih_plug::prelude::*;
+use std::f32::consts;
+
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+pub(crate) enum FilterMode {
+    Off,
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Enum)]
+pub(crate) enum FilterSlope {
+    #[name = "12 dB"]
+    Db12,
+    #[name = "24 dB"]
+    Db24,
+}
+
+// Q of the filter for a resonance between 0 and 1.
+pub(crate) fn q(resonance: f32) -> f32 {
+    consts::FRAC_1_SQRT_2 + resonance * 10.0
+}
+
+// Cutoff the filter actually runs at, kept clear of DC and Nyquist.
+pub(crate) fn clamp_cutoff(cutoff: f32, sample_rate: f32) -> f32 {
+    cutoff.clamp(20.0, sample_rate * 0.49)
+}
+
+// Coefficients shared by every stage of a voice's filter for one sample.
+pub(crate) struct SvfCoefficients {
+    g: f32,
+    k: f32,
+    a1: f32,
+}
+
+impl SvfCoefficients {
+    pub(crate) fn new(cutoff: f32, resonance: f32, sample_rate: f32) -> Self {
+        let cutoff = clamp_cutoff(cutoff, sample_rate);
+        let g = (consts::PI * cutoff / sample_rate).tan();
+        let k = 1.0 / q(resonance);
+        Self {
+            g,
+            k,
+            a1: 1.0 / (1.0 + g * (g + k)),
+        }
+    }
+}
+
+// Trapezoidal state variable filter after Andrew Simper's "Linear Trap Integrated SVF".
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Svf {
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl Svf {
+    pub(crate) fn process(&mut self, input: f32, mode: FilterMode, coefficients: &SvfCoefficients) -> f32 {
+        let SvfCoefficients { g, k, a1 } = *coefficients;
+        let v1 = a1 * (self.ic1eq + g * (input - self.ic2eq));
+        let v2 = self.ic2eq + g * v1;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+        match mode {
+            FilterMode::Off => input,
+            FilterMode::LowPass => v2,
+            FilterMode::HighPass => input - k * v1 - v2,
+            // Scaled to unity gain at the centre.
+            FilterMode::BandPass => k * v1,
+            FilterMode::Notch => input - k * v1,
+        }
+    }
+}